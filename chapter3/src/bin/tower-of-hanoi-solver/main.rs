@@ -0,0 +1,1341 @@
+//! A command-line front end to the `hanoi` module of this package, which
+//! solves the puzzle, lets a person or a bot play it, and draws, records,
+//! replays, checks, and explores games.
+
+use part3::hanoi::cast::CastRecorder;
+use part3::hanoi::gif::GifEncoder;
+use part3::hanoi::graph::StateGraph;
+use part3::hanoi::notation::{self, CheckMovesError, MoveRecorder};
+use part3::hanoi::packed::PackedState;
+use part3::hanoi::random::{Position, Rng};
+use part3::hanoi::render::{self, Animated, Render, Style};
+use part3::hanoi::rules::{DigraphSolver, Rules};
+use part3::hanoi::svg::{self, SvgFrame, SvgStyle};
+use part3::hanoi::trace::Trace;
+use part3::hanoi::variant::{Variant, MAX_SEARCH_HEIGHT};
+use part3::hanoi::{bot, gif};
+use part3::hanoi::{
+    distance_to_tower, moves_bitwise, moves_emulate_recursion, moves_iterative, moves_made,
+    nth_move, play, play_with, solve, solve_from, state_after, FrameStewart, Move, Session, Solver,
+    TowerSelector, TowerSelectorSet, TowerSet,
+};
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::net::{Ipv4Addr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::Duration;
+use std::{fmt, io};
+
+/// Prints a frame showing the towers, given the latest move (if any).
+fn print_frame(renderer: &dyn Render, last: Option<Move>, towers: &TowerSet) {
+    let mut out = io::stdout().lock();
+    renderer
+        .render(&mut out, towers, last)
+        .and_then(|()| out.flush())
+        .expect("failed printing to stdout");
+}
+
+/// Prints the towers in the configured style, below a blank line if the
+/// style takes several lines.
+fn print_towers(config: &Config, last: Option<Move>, towers: &TowerSet) {
+    let renderer = config.renderer();
+    if renderer.is_multiline() {
+        println!();
+    }
+    print_frame(&*renderer, last, towers);
+}
+
+/// Prints the towers in place of the previous output, and pauses.
+fn animate(config: &Config, last: Option<Move>, towers: &TowerSet) {
+    print_frame(&Animated(&*config.renderer()), last, towers);
+    sleep(config.delay);
+}
+
+/// Prints the progress of a solver in the configured output format, given
+/// the latest move (if any) and the towers after it.
+fn show(config: &Config, last: Option<Move>, towers: &TowerSet) {
+    if config.quiet {
+        return;
+    }
+    match (config.output, last) {
+        (Output::Towers, _) => print_towers(config, last, towers),
+        (Output::Animate, _) => animate(config, last, towers),
+        (Output::Moves, Some((source, target))) => println!("{source}{target}"),
+        (Output::Moves, None) => {}
+    }
+}
+
+/// Shows the progress of a solver, either on the terminal or, if so
+/// configured, in a recording of the animation.  Recording takes no time, and
+/// any error writing the recording is kept until `finish`, so that solvers
+/// need not check after every move.
+struct Viewer<'a> {
+    config: &'a Config,
+    recorder: Option<CastRecorder<io::BufWriter<File>>>,
+    error: Option<io::Error>,
+}
+
+impl Viewer<'_> {
+    fn new(config: &Config) -> io::Result<Viewer<'_>> {
+        let recorder = match &config.record {
+            Some(path) => Some(CastRecorder::new(
+                io::BufWriter::new(File::create(path)?),
+                config.delay,
+            )),
+            None => None,
+        };
+        Ok(Viewer {
+            config,
+            recorder,
+            error: None,
+        })
+    }
+
+    /// Shows the latest move (if any) and the towers after it.
+    fn show(&mut self, last: Option<Move>, towers: &TowerSet) {
+        let Some(recorder) = &mut self.recorder else {
+            return show(self.config, last, towers);
+        };
+        if self.error.is_none() {
+            let mut frame = Vec::new();
+            let result = Animated(&*self.config.renderer())
+                .render(&mut frame, towers, last)
+                .and_then(|()| recorder.frame(&String::from_utf8_lossy(&frame)));
+            self.error = result.err();
+        }
+    }
+
+    /// Finishes the recording, if any, or prints the final towers if quiet.
+    fn finish(self, towers: &TowerSet) -> io::Result<()> {
+        let Some(recorder) = self.recorder else {
+            if self.config.quiet {
+                print_frame(&*self.config.renderer(), None, towers);
+            }
+            return Ok(());
+        };
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        let frames = recorder.frames();
+        recorder.finish()?;
+        if let Some(path) = &self.config.record {
+            println!("Recorded {frames} frames in {}", path.display());
+        }
+        Ok(())
+    }
+}
+
+fn try_move_command(
+    session: &mut Session,
+    source: &str,
+    target: &str,
+) -> Result<(), Box<dyn Error>> {
+    Ok(session.play(source.parse()?, target.parse()?)?)
+}
+
+fn interact(mut session: Session, config: &Config) -> Result<Session, Box<dyn Error>> {
+    let mut lines = io::stdin().lines();
+    while !session.is_won() {
+        print_towers(config, session.history.last().copied(), &session.towers);
+        println!("\n{}", session.progress());
+        let on_path = (session.target == config.target)
+            .then(|| moves_made(&session.towers, config.selectors()))
+            .flatten();
+        if let Some(k @ 1..) = on_path {
+            println!("This is the position after move {k} of the optimal solution.");
+        }
+        let labels: Vec<_> = session.towers.selectors().map(|s| s.to_string()).collect();
+        println!(
+            "\nEnter letter of start tower and the end tower. ({}), or U to undo, R to redo, H for a hint, S to solve from here, SAVE or LOAD followed by a file name, or Q to quit.",
+            labels.join(", ")
+        );
+        let Some(line) = lines.next() else {
+            break; // End of input.
+        };
+        let line = line?;
+        // File names are case sensitive, so check for them before converting
+        // the rest of the line to upper case.
+        match line.trim().split_once(char::is_whitespace) {
+            Some((command, path)) if command.eq_ignore_ascii_case("save") => {
+                match session.save(Path::new(path.trim())) {
+                    Ok(()) => println!("\nSaved to {}", path.trim()),
+                    Err(err) => eprintln!("warning: {err}"),
+                }
+                continue;
+            }
+            Some((command, path)) if command.eq_ignore_ascii_case("load") => {
+                match Session::load(Path::new(path.trim())) {
+                    Ok(loaded) => session = loaded,
+                    Err(err) => eprintln!("warning: {err}"),
+                }
+                continue;
+            }
+            _ => {}
+        }
+        let line = line.to_uppercase();
+        let line = line.trim();
+        if line.starts_with('Q') {
+            break;
+        }
+        match line.len() {
+            0 => {
+                // Ignore empty line.
+            }
+            1 if line.starts_with('U') => {
+                if session.undo().is_none() {
+                    eprintln!("warning: nothing to undo");
+                }
+            }
+            1 if line.starts_with('R') => {
+                if session.redo().is_none() {
+                    eprintln!("warning: nothing to redo");
+                }
+            }
+            1 if line.starts_with('H') => match session.hint() {
+                Some((source, target)) => println!("\nHint: {source}{target}"),
+                None => eprintln!("warning: hints require three towers"),
+            },
+            1 if line.starts_with('S') => {
+                if session.towers.towers.len() != 3 {
+                    eprintln!("warning: solving from here requires three towers");
+                    continue;
+                }
+                for (source, target) in solve_from(&session.towers, session.target) {
+                    session.play(source, target)?;
+                    animate(config, Some((source, target)), &session.towers);
+                }
+            }
+            2 => {
+                if let Err(err) = try_move_command(&mut session, &line[..1], &line[1..2]) {
+                    eprintln!("warning: {err}");
+                }
+            }
+            _ => {
+                eprintln!("warning: bad command");
+            }
+        }
+    }
+    print_towers(config, session.history.last().copied(), &session.towers);
+    println!("\n{}", session.summary());
+    Ok(session)
+}
+
+/// How the solver reports its progress.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Output {
+    /// Print the towers after each move, each below the last.
+    Towers,
+    /// Redraw the towers in place after each move, pausing between moves.
+    Animate,
+    /// Print each move as a pair of tower labels, one per line.
+    Moves,
+}
+
+impl FromStr for Output {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "towers" => Ok(Output::Towers),
+            "animate" => Ok(Output::Animate),
+            "moves" => Ok(Output::Moves),
+            _ => Err(format!("{s}: bad output mode")),
+        }
+    }
+}
+
+/// What the program does, as chosen by command-line flags.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Mode {
+    Solve,
+    Interact,
+    After(u64),
+    /// Show the moves listed in a file.
+    Replay(PathBuf),
+    /// Check whether the moves listed in a file solve the puzzle.
+    Check(PathBuf),
+    /// Describe the graph of every state and move.
+    Explore,
+    /// Show every call the recursive solution makes.
+    Trace,
+    /// Referee a game with a bot over standard input and output, or over a
+    /// TCP connection on this port of the loopback interface.
+    Bot(Option<u16>),
+    /// Prompt the user for moves from a random position.
+    Puzzle,
+    /// Show this many random positions and how far each is from the target.
+    Drills(usize),
+    Help,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+struct UsageError(String);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for UsageError {}
+
+/// The most disks whose state graph can be explored.  Finding the diameter
+/// takes a search from each of the 3^n states, so time grows as 9^n.
+const MAX_EXPLORE_DISKS: usize = 8;
+
+/// The most disks whose recursive calls can be traced.  The solution makes
+/// 2^(n+1) - 1 calls, each shown on a line of its own.
+const MAX_TRACE_DISKS: usize = 10;
+
+/// The most disks in a random position.  Its distance from a tower, up to
+/// 2^n - 1 moves, must fit in a `u64`.
+const MAX_RANDOM_DISKS: usize = 64;
+
+/// Settings parsed once from the command line, and passed to whatever needs
+/// them.
+#[derive(Debug, Eq, PartialEq)]
+struct Config {
+    mode: Mode,
+    disks: usize,
+    pegs: usize,
+    source: TowerSelector,
+    target: TowerSelector,
+    buffer: TowerSelector,
+    delay: Duration,
+    output: Output,
+    quiet: bool,
+    solver: Solver,
+    /// Which moves between towers are allowed.
+    rules: Rules,
+    /// Disks of the same size to start with, and the goal for them.
+    variant: Option<Variant>,
+    /// A saved game to start from, rather than a complete tower.
+    load: Option<PathBuf>,
+    /// A file in which to list the solver's moves.
+    write_moves: Option<PathBuf>,
+    /// A file in which to record the animation, rather than showing it.
+    record: Option<PathBuf>,
+    /// How to draw the towers.
+    style: Style,
+    /// The number of columns to fit the towers in, if known.
+    width: Option<usize>,
+    /// A file in which to draw the moves as scalable vector graphics.
+    svg: Option<PathBuf>,
+    svg_frame: SvgFrame,
+    svg_style: SvgStyle,
+    /// A file in which to draw the moves as an animated GIF.
+    gif: Option<PathBuf>,
+    /// A file in which to write the graph of every state, or the tree of
+    /// recursive calls, for Graphviz.
+    dot: Option<PathBuf>,
+    /// The seed for random positions, rather than one based on the time.
+    seed: Option<u64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let TowerSelectorSet {
+            source,
+            target,
+            buffer,
+        } = TowerSelectorSet::new();
+        Config {
+            mode: Mode::Solve,
+            disks: 6,
+            pegs: 3,
+            source,
+            target,
+            buffer,
+            delay: Duration::from_millis(500),
+            output: Output::Towers,
+            quiet: false,
+            solver: Solver::Recursive,
+            rules: Rules::Classic,
+            variant: None,
+            load: None,
+            write_moves: None,
+            record: None,
+            style: Style::Ascii,
+            width: None,
+            svg: None,
+            svg_frame: SvgFrame::Animated,
+            svg_style: SvgStyle::default(),
+            gif: None,
+            dot: None,
+            seed: None,
+        }
+    }
+}
+
+/// Parses the value of a flag, explaining which flag was at fault on failure.
+fn parse_value<T>(flag: &str, value: Option<String>) -> Result<T, UsageError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let value = value.ok_or_else(|| UsageError(format!("{flag}: missing value")))?;
+    value
+        .parse()
+        .map_err(|err| UsageError(format!("{flag}: {err}")))
+}
+
+impl Config {
+    /// Parses command-line arguments, not including the program name.
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Config, UsageError> {
+        let mut config = Config::default();
+        let mut mode = None;
+        let mut output = None;
+        let mut buffer = None;
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            let mut set_mode = |new: Mode| match &mode {
+                Some(old) if *old != new => Err(UsageError(format!(
+                    "{flag}: conflicts with an earlier flag"
+                ))),
+                _ => {
+                    mode = Some(new);
+                    Ok(())
+                }
+            };
+            match flag.as_str() {
+                "-a" | "--animate" => output = Some(Output::Animate),
+                "-b" | "--buffer" => buffer = Some(parse_value(&flag, args.next())?),
+                "-d" | "--delay" => {
+                    config.delay = Duration::from_millis(parse_value(&flag, args.next())?)
+                }
+                "-h" | "--help" => set_mode(Mode::Help)?,
+                "-x" | "--explore" => set_mode(Mode::Explore)?,
+                "-T" | "--trace" => set_mode(Mode::Trace)?,
+                "--bot" => set_mode(Mode::Bot(None))?,
+                "-P" | "--puzzle" => set_mode(Mode::Puzzle)?,
+                "--drills" => set_mode(Mode::Drills(parse_value(&flag, args.next())?))?,
+                "--seed" => config.seed = Some(parse_value(&flag, args.next())?),
+                "--listen" => set_mode(Mode::Bot(Some(parse_value(&flag, args.next())?)))?,
+                "--dot" => config.dot = Some(parse_value(&flag, args.next())?),
+                "-i" | "--interact" | "--interactive" => set_mode(Mode::Interact)?,
+                "-k" | "--after" => set_mode(Mode::After(parse_value(&flag, args.next())?))?,
+                "-c" | "--check" => set_mode(Mode::Check(parse_value(&flag, args.next())?))?,
+                "-r" | "--replay" => set_mode(Mode::Replay(parse_value(&flag, args.next())?))?,
+                "-R" | "--record" => config.record = Some(parse_value(&flag, args.next())?),
+                "-g" | "--svg" => config.svg = Some(parse_value(&flag, args.next())?),
+                "-G" | "--gif" => config.gif = Some(parse_value(&flag, args.next())?),
+                "--svg-frame" => config.svg_frame = parse_value(&flag, args.next())?,
+                "--svg-size" => {
+                    let size: String = parse_value(&flag, args.next())?;
+                    let (width, height) = size
+                        .split_once('x')
+                        .and_then(|(width, height)| {
+                            Some((width.parse().ok()?, height.parse().ok()?))
+                        })
+                        .filter(|&(width, height)| width > 0 && height > 0)
+                        .ok_or_else(|| UsageError(format!("{flag}: {size}: bad size")))?;
+                    (config.svg_style.width, config.svg_style.height) = (width, height);
+                }
+                "--svg-colours" => {
+                    let colours: String = parse_value(&flag, args.next())?;
+                    let mut colours = colours.split(',').map(str::to_string);
+                    match (colours.next(), colours.next(), colours.collect::<Vec<_>>()) {
+                        (Some(background), Some(pegs), disks) if !disks.is_empty() => {
+                            config.svg_style.background = background;
+                            config.svg_style.pegs = pegs;
+                            config.svg_style.disks = disks;
+                        }
+                        _ => {
+                            return Err(UsageError(format!(
+                                "{flag}: expected background, pegs, and at least one disk colour"
+                            )))
+                        }
+                    }
+                }
+                "-y" | "--style" => config.style = parse_value(&flag, args.next())?,
+                "-W" | "--width" => config.width = Some(parse_value(&flag, args.next())?),
+                "-w" | "--write-moves" => {
+                    config.write_moves = Some(parse_value(&flag, args.next())?)
+                }
+                "-l" | "--load" => config.load = Some(parse_value(&flag, args.next())?),
+                "-m" | "--moves" => output = Some(Output::Moves),
+                "-n" | "--disks" => config.disks = parse_value(&flag, args.next())?,
+                "-o" | "--output" => output = Some(parse_value(&flag, args.next())?),
+                "-p" | "--pegs" => config.pegs = parse_value(&flag, args.next())?,
+                "-q" | "--quiet" => config.quiet = true,
+                "-s" | "--solver" => config.solver = parse_value(&flag, args.next())?,
+                "-u" | "--rules" => config.rules = parse_value(&flag, args.next())?,
+                "-V" | "--variant" => config.variant = Some(parse_value(&flag, args.next())?),
+                "-S" | "--source" => config.source = parse_value(&flag, args.next())?,
+                "-t" | "--target" => config.target = parse_value(&flag, args.next())?,
+                _ => return Err(UsageError(format!("{flag}: unknown flag"))),
+            }
+        }
+        config.mode = mode.unwrap_or(Mode::Solve);
+        // Replays are animated unless otherwise specified.
+        config.output = output.unwrap_or(match config.mode {
+            Mode::Replay(_) => Output::Animate,
+            _ => Output::Towers,
+        });
+        // Unless otherwise specified, the buffer is the first tower that is
+        // neither the source nor the target.
+        config.buffer = buffer.unwrap_or_else(|| {
+            (0..)
+                .map(TowerSelector)
+                .find(|&selector| selector != config.source && selector != config.target)
+                .expect("some tower should be free")
+        });
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), UsageError> {
+        if !(3..=TowerSelector::MAX).contains(&self.pegs) {
+            return Err(UsageError(format!(
+                "peg count should be from 3 to {}: {}",
+                TowerSelector::MAX,
+                self.pegs
+            )));
+        }
+        for selector in [self.source, self.target, self.buffer] {
+            if selector.0 >= self.pegs {
+                return Err(UsageError(format!(
+                    "there is no tower {selector} among {} pegs",
+                    self.pegs
+                )));
+            }
+        }
+        let Config {
+            source,
+            target,
+            buffer,
+            ..
+        } = self;
+        if source == target || source == buffer || target == buffer {
+            return Err(UsageError(format!(
+                "source, target, and buffer should differ: {source}, {target}, {buffer}"
+            )));
+        }
+        if self.load.is_some() && !matches!(self.mode, Mode::Solve | Mode::Interact) {
+            return Err(UsageError(
+                "only solving and interactive play can start from a saved game".to_string(),
+            ));
+        }
+        if self.record.is_some() && !matches!(self.mode, Mode::Solve | Mode::Replay(_)) {
+            return Err(UsageError(
+                "only solutions and replays can be recorded".to_string(),
+            ));
+        }
+        if (self.svg.is_some() || self.gif.is_some())
+            && (self.load.is_some() || !matches!(self.mode, Mode::Solve | Mode::Replay(_)))
+        {
+            return Err(UsageError(
+                "only solutions and replays can be drawn".to_string(),
+            ));
+        }
+        if self.write_moves.is_some() && !matches!(self.mode, Mode::Solve | Mode::Bot(_)) {
+            return Err(UsageError(
+                "only the moves of the solver or a bot can be written".to_string(),
+            ));
+        }
+        if self.dot.is_some() && !matches!(self.mode, Mode::Explore | Mode::Trace) {
+            return Err(UsageError(
+                "only explored graphs and traces can be written".to_string(),
+            ));
+        }
+        if self.mode == Mode::Explore && self.disks > MAX_EXPLORE_DISKS {
+            return Err(UsageError(format!(
+                "exploring supports at most {MAX_EXPLORE_DISKS} disks"
+            )));
+        }
+        if self.seed.is_some() && !matches!(self.mode, Mode::Puzzle | Mode::Drills(_)) {
+            return Err(UsageError("only puzzles and drills use a seed".to_string()));
+        }
+        if matches!(self.mode, Mode::Puzzle | Mode::Drills(_)) && self.disks > MAX_RANDOM_DISKS {
+            return Err(UsageError(format!(
+                "random positions support at most {MAX_RANDOM_DISKS} disks"
+            )));
+        }
+        if self.mode == Mode::Trace {
+            if self.disks > MAX_TRACE_DISKS {
+                return Err(UsageError(format!(
+                    "tracing supports at most {MAX_TRACE_DISKS} disks"
+                )));
+            }
+            if self.solver != Solver::Recursive {
+                return Err(UsageError(
+                    "only the recursive solver can be traced".to_string(),
+                ));
+            }
+        }
+        if self.pegs != 3 {
+            if let Mode::After(_) = self.mode {
+                return Err(UsageError("skipping moves requires three pegs".to_string()));
+            }
+            if self.mode == Mode::Explore {
+                return Err(UsageError("exploring requires three pegs".to_string()));
+            }
+            if self.mode == Mode::Trace {
+                return Err(UsageError("tracing requires three pegs".to_string()));
+            }
+            if let Mode::Bot(_) = self.mode {
+                return Err(UsageError("bot games require three pegs".to_string()));
+            }
+            if let Mode::Puzzle | Mode::Drills(_) = self.mode {
+                return Err(UsageError(
+                    "random positions require three pegs".to_string(),
+                ));
+            }
+            if self.solver != Solver::Recursive {
+                return Err(UsageError(format!(
+                    "the {:?} solver requires three pegs",
+                    self.solver
+                )));
+            }
+        }
+        if self.rules != Rules::Classic {
+            if self.pegs != 3 {
+                return Err(UsageError(format!(
+                    "the {} rules require three pegs",
+                    self.rules
+                )));
+            }
+            if self.load.is_some()
+                || !matches!(self.mode, Mode::Solve | Mode::Replay(_) | Mode::Check(_))
+            {
+                return Err(UsageError(format!(
+                    "the {} rules apply only to solving, replaying, and checking",
+                    self.rules
+                )));
+            }
+            if self.solver != Solver::Recursive {
+                return Err(UsageError(format!(
+                    "the {:?} solver requires the classic rules",
+                    self.solver
+                )));
+            }
+        }
+        if let Some(variant) = self.variant {
+            if self.pegs != 3 {
+                return Err(UsageError(format!(
+                    "the {variant} variant requires three pegs"
+                )));
+            }
+            if self.load.is_some() || self.mode != Mode::Solve {
+                return Err(UsageError(format!(
+                    "the {variant} variant applies only to solving"
+                )));
+            }
+            if self.rules != Rules::Classic || self.solver != Solver::Recursive {
+                return Err(UsageError(format!(
+                    "the {variant} variant requires the classic rules and solver"
+                )));
+            }
+        }
+        let searched = self.variant.is_some_and(Variant::is_searched) || self.rules.is_searched();
+        if searched && self.disks > MAX_SEARCH_HEIGHT {
+            return Err(UsageError(format!(
+                "searching for solutions supports at most {MAX_SEARCH_HEIGHT} disk sizes"
+            )));
+        }
+        let gif_disks = gif::max_disks(GIF_SIZE.1.into());
+        if self.gif.is_some() && self.towers().disks().count() > gif_disks {
+            return Err(UsageError(format!(
+                "GIFs can show at most {gif_disks} disks"
+            )));
+        }
+        if self.solver == Solver::Bitwise && self.disks > 64 {
+            return Err(UsageError(
+                "the bitwise solver supports at most 64 disks".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn selectors(&self) -> TowerSelectorSet {
+        TowerSelectorSet::from_parts(self.source, self.target, self.buffer)
+    }
+
+    /// Returns a renderer in the configured style.
+    fn renderer(&self) -> Box<dyn Render> {
+        self.style.renderer(self.width)
+    }
+
+    /// Returns the source, target, and buffer, followed by any other towers,
+    /// as expected by the Frame–Stewart solver.
+    fn all_selectors(&self) -> Vec<TowerSelector> {
+        let first = [self.source, self.target, self.buffer];
+        let rest = (0..self.pegs)
+            .map(TowerSelector)
+            .filter(|selector| !first.contains(selector));
+        first.into_iter().chain(rest).collect()
+    }
+
+    /// Returns the starting towers, with every disk on the source tower.
+    fn towers(&self) -> TowerSet {
+        match self.variant {
+            Some(variant) => variant.start(self.disks, self.source),
+            None => TowerSet::from_positions(self.pegs, &vec![self.source; self.disks]),
+        }
+    }
+}
+
+fn print_help() {
+    println!("Usage:\n");
+    println!("    tower-of-hanoi-solver [flag...]\n");
+    println!("Flags:\n");
+    println!("    -a|--animate          same as --output animate");
+    println!("    -b|--buffer X         use tower X as the buffer (default: first free tower)");
+    println!("    --bot                 referee a game with a bot over standard input and");
+    println!("                          output, and report its score on standard error");
+    println!("    -c|--check FILE       check that the moves listed in FILE solve the puzzle");
+    println!("    -d|--delay MS         pause MS milliseconds between frames (default 500)");
+    println!("    --drills N            show N random positions, and the fewest moves from");
+    println!("                          each to the target");
+    println!("    -g|--svg FILE         draw the moves in FILE as scalable vector graphics");
+    println!("    --svg-frame FRAME     animated (default), or just the first or last frame");
+    println!("    --svg-size WxH        the size of the picture (default 640x240)");
+    println!("    --svg-colours LIST    colours of the background, the pegs, and then the");
+    println!("                          disks from the smallest up, separated by commas");
+    println!("    --dot FILE            with --explore, write the state graph in FILE for");
+    println!("                          Graphviz; with --trace, the tree of calls");
+    println!("    -G|--gif FILE         draw the moves in FILE as an animated GIF");
+    println!("    -h|--help             show this message");
+    println!("    -i|--interact         prompt the user for moves");
+    println!("    -k|--after K          show the Kth move and the towers after it");
+    println!("    -l|--load FILE        start from a saved game, and solve it from there");
+    println!("    --listen PORT         referee a game with a bot that connects to PORT on");
+    println!("                          localhost (0 for any free port)");
+    println!("    -m|--moves            same as --output moves");
+    println!("    -n|--disks N          start with N disks (default 6)");
+    println!("    -o|--output MODE      towers (default), animate, or moves");
+    println!("    -p|--pegs N           use N pegs (default 3)");
+    println!("    -P|--puzzle           prompt the user for moves from a random position,");
+    println!("                          and score them against the fewest possible");
+    println!("    -q|--quiet            show only the final towers");
+    println!("    -r|--replay FILE      show the moves listed in FILE (animated by default)");
+    println!("    -R|--record FILE      record the animation in FILE, in asciinema v2 format,");
+    println!("                          rather than showing it");
+    println!("    -s|--solver NAME      recursive (default), iterative, bitwise, or");
+    println!("                          emulate-recursion");
+    println!("    --seed N              draw random positions from seed N (default: one");
+    println!("                          based on the time, which is shown)");
+    println!("    -S|--source X         start with the disks on tower X (default A)");
+    println!("    -t|--target X         move the disks to tower X (default B)");
+    println!("    -T|--trace            show each call of the recursive solution, and how");
+    println!("                          many there are at each depth");
+    println!("    -u|--rules RULES      classic (default), cyclic (A to B to C to A only),");
+    println!("                          adjacent (never directly between A and C), magnetic");
+    println!("                          (disks flip, and like faces repel), or a list of");
+    println!("                          allowed moves such as AB,BC,CA,BA");
+    println!("    -V|--variant NAME     copies:K (K disks of each size), ordered-copies:K");
+    println!("                          (which must keep their order), or bicolor (white");
+    println!("                          disks to the target, black disks to the buffer)");
+    println!("    -x|--explore          describe the graph of every state and move");
+    println!("    -w|--write-moves FILE list the moves of the solver or a bot in FILE");
+    println!("    -W|--width COLS       narrow the towers to fit COLS columns (default:");
+    println!("                          $COLUMNS, if set)");
+    println!("    -y|--style NAME       draw the towers as ascii (default), unicode, compact,");
+    println!("                          or json");
+}
+
+/// Shows the fewest moves that win a saved game, from where it left off.
+fn solve_saved(path: &Path, config: &Config) -> Result<(), Box<dyn Error>> {
+    let session = Session::load(path)?;
+    let towers = session.towers;
+    if towers.towers.len() != 3 {
+        return Err("solving a saved game requires three pegs".into());
+    }
+    let mut viewer = Viewer::new(config)?;
+    viewer.show(None, &towers);
+    let moves = solve_from(&towers, session.target);
+    let towers = play(towers, moves, &mut |last, towers: &TowerSet| {
+        viewer.show(Some(last), towers)
+    })?;
+    viewer.finish(&towers)?;
+    Ok(())
+}
+
+/// Draws the moves from the starting towers, as configured.
+fn export_svg(
+    path: &Path,
+    config: &Config,
+    start: &TowerSet,
+    moves: &[Move],
+) -> Result<(), Box<dyn Error>> {
+    let style = SvgStyle {
+        move_duration: config.delay,
+        ..config.svg_style.clone()
+    };
+    let svg = svg::draw(start, moves, config.svg_frame, &style)?;
+    std::fs::write(path, svg)?;
+    Ok(())
+}
+
+/// The size of animated GIFs, in pixels.
+const GIF_SIZE: (u16, u16) = (480, 180);
+
+/// Draws the moves from the starting towers as an animated GIF, showing each
+/// frame for the configured delay, and the last for longer before it repeats.
+fn export_gif(
+    path: &Path,
+    config: &Config,
+    start: &TowerSet,
+    moves: &[Move],
+) -> Result<(), Box<dyn Error>> {
+    let (width, height) = GIF_SIZE;
+    let out = io::BufWriter::new(File::create(path)?);
+    let palette = gif::palette(start.disks().count());
+    let mut encoder = GifEncoder::new(out, width, height, &palette)?;
+    let mut towers = start.clone();
+    let frame = |encoder: &mut GifEncoder<_>, towers: &TowerSet, delay| {
+        encoder.frame(&gif::rasterise(towers, width.into(), height.into()), delay)
+    };
+    for &(source, target) in moves {
+        frame(&mut encoder, &towers, config.delay)?;
+        towers.move_disk(source, target)?;
+    }
+    frame(&mut encoder, &towers, config.delay * 4)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// Describes the graph of every state and move, and the shortest path from
+/// the configured towers to the target.
+fn explore(config: &Config) -> Result<(), Box<dyn Error>> {
+    let graph = StateGraph::new(config.disks);
+    let start = graph.state(PackedState::try_from(&config.towers())?);
+    let goal = graph.state(PackedState::new(3, config.disks, config.target)?);
+    println!(
+        "{} states, joined by {} moves",
+        graph.state_count(),
+        graph.edge_count()
+    );
+    println!("Diameter: {} moves", graph.diameter());
+    println!(
+        "Eccentricity of the start: {} moves",
+        graph.eccentricity(start)
+    );
+    let path = graph.shortest_path(start, goal);
+    println!(
+        "Shortest path to tower {}: {} moves",
+        config.target,
+        path.len() - 1
+    );
+    if let Some(path) = &config.dot {
+        let mut dot = String::new();
+        graph.write_dot(&mut dot)?;
+        std::fs::write(path, dot)?;
+    }
+    Ok(())
+}
+
+/// Shows each call the recursive solution makes, indented by depth, then the
+/// number of calls at each depth.
+fn trace(config: &Config) -> Result<(), Box<dyn Error>> {
+    let trace = Trace::new(config.disks, config.selectors());
+    let mut text = String::new();
+    trace.write_tree(&mut text)?;
+    text.push('\n');
+    trace.write_depth_table(&mut text)?;
+    print!("{text}");
+    if let Some(path) = &config.dot {
+        let mut dot = String::new();
+        trace.write_dot(&mut dot)?;
+        std::fs::write(path, dot)?;
+    }
+    Ok(())
+}
+
+/// Referees a game with a bot, over standard input and output or a
+/// connection to the specified port, and reports its score.
+fn play_bot(config: &Config, port: Option<u16>) -> Result<(), Box<dyn Error>> {
+    let session = Session::new(config.towers(), config.target);
+    let renderer: &dyn Render = match config.style {
+        Style::Json => &render::Json,
+        _ => &render::Compact,
+    };
+    let game = match port {
+        None => {
+            let game = bot::referee(session, renderer, io::stdin().lock(), io::stdout())?;
+            // Standard output belongs to the bot.
+            eprintln!("{game}");
+            game
+        }
+        Some(port) => {
+            let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+            eprintln!("Listening on {}", listener.local_addr()?);
+            let (stream, peer) = listener.accept()?;
+            eprintln!("Playing against {peer}");
+            let input = io::BufReader::new(stream.try_clone()?);
+            let game = bot::referee(session, renderer, input, stream)?;
+            println!("{game}");
+            game
+        }
+    };
+    if let Some(path) = &config.write_moves {
+        let mut recorder = MoveRecorder::new(io::BufWriter::new(File::create(path)?));
+        recorder.comment(&format!(
+            "{} disks from tower {} to tower {}, played by a bot",
+            config.disks, config.source, config.target
+        ))?;
+        for &next in &game.session.history {
+            recorder.record(next)?;
+        }
+        recorder.finish()?;
+    }
+    Ok(())
+}
+
+/// Draws random positions from the configured seed, or one based on the
+/// time, and either shows them as drills or starts a puzzle from one.
+fn play_random(config: &Config) -> Result<(), Box<dyn Error>> {
+    let seed = config.seed.unwrap_or_else(Rng::seed_from_time);
+    println!("Seed: {seed}");
+    let mut rng = Rng::new(seed);
+    let (disks, target) = (config.disks, config.target);
+    if let Mode::Drills(count) = config.mode {
+        for number in 1..=count {
+            let position = Position::random(&mut rng, disks, target);
+            println!(
+                "\nDrill {number}: {} moves to tower {target}",
+                position.distance
+            );
+            print_towers(config, None, &position.towers);
+        }
+        return Ok(());
+    }
+    // A puzzle that is already solved is no puzzle.
+    let position = loop {
+        let position = Position::random(&mut rng, disks, target);
+        if position.distance > 0 || disks == 0 {
+            break position;
+        }
+    };
+    println!(
+        "Gather every disk on tower {target} in as few moves as you can; it can be done in {}.\n",
+        position.distance
+    );
+    let session = interact(Session::new(position.towers, target), config)?;
+    println!("Score: {}/100", session.score());
+    Ok(())
+}
+
+/// Reads a list of moves in the notation of the `notation` module.
+fn read_moves(path: &Path) -> Result<Vec<Move>, Box<dyn Error>> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(notation::parse_moves(&text).map_err(|err| format!("{}:{err}", path.display()))?)
+}
+
+/// Shows the moves listed in a file, starting from the configured towers.
+fn replay(path: &Path, config: &Config) -> Result<(), Box<dyn Error>> {
+    let moves = read_moves(path)?;
+    let mut towers = config.towers();
+    let mut viewer = Viewer::new(config)?;
+    viewer.show(None, &towers);
+    for (index, &(source, target)) in moves.iter().enumerate() {
+        towers
+            .move_disk_with(&config.rules, source, target)
+            .map_err(|error| CheckMovesError::IllegalMove {
+                number: index + 1,
+                next: (source, target),
+                error,
+            })?;
+        viewer.show(Some((source, target)), &towers);
+    }
+    viewer.finish(&towers)?;
+    if let Some(svg) = &config.svg {
+        export_svg(svg, config, &config.towers(), &moves)?;
+    }
+    if let Some(gif) = &config.gif {
+        export_gif(gif, config, &config.towers(), &moves)?;
+    }
+    Ok(())
+}
+
+/// Checks the moves listed in a file, reporting whether they solve the
+/// puzzle, and exiting with an error status if they do not.
+fn check(path: &Path, config: &Config) -> Result<(), Box<dyn Error>> {
+    let moves = read_moves(path)?;
+    let towers = config.towers();
+    let optimal = match config.rules {
+        _ if config.pegs != 3 => None,
+        Rules::Classic => distance_to_tower(&towers.positions(), config.target),
+        Rules::Magnetic => Rules::Magnetic
+            .moves(config.disks, config.source, config.target)
+            .map(|moves| moves.count() as u64),
+        ref rules => DigraphSolver::new(&rules.arcs(), config.disks).count(
+            config.disks,
+            config.source,
+            config.target,
+        ),
+    };
+    match notation::check_moves(towers, &moves, &config.rules, config.target) {
+        Ok(_) => {
+            print!("{}: solved in {} moves", path.display(), moves.len());
+            match optimal {
+                Some(optimal) if optimal == moves.len() as u64 => println!(", which is optimal"),
+                Some(optimal) => println!(" (optimal: {optimal})"),
+                None => println!(),
+            }
+            Ok(())
+        }
+        Err(err) => {
+            eprintln!("{}: {err}", path.display());
+            std::process::exit(1);
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut config = match Config::parse(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("error: {err}");
+            eprintln!("Try --help for more information.");
+            std::process::exit(2);
+        }
+    };
+    if config.width.is_none() {
+        // Shells set COLUMNS, though they seldom export it.
+        config.width = std::env::var("COLUMNS").ok().and_then(|s| s.parse().ok());
+    }
+    let towers = config.towers();
+    let (disks, selectors) = (config.disks, config.selectors());
+    match &config.mode {
+        Mode::Help => print_help(),
+        Mode::Replay(path) => replay(path, &config)?,
+        Mode::Check(path) => check(path, &config)?,
+        Mode::Explore => explore(&config)?,
+        Mode::Trace => trace(&config)?,
+        &Mode::Bot(port) => play_bot(&config, port)?,
+        Mode::Puzzle | Mode::Drills(_) => play_random(&config)?,
+        Mode::Interact => {
+            let session = match &config.load {
+                Some(path) => Session::load(path)?,
+                None => Session::new(towers, config.target),
+            };
+            interact(session, &config)?;
+        }
+        &Mode::After(k) => {
+            let towers = state_after(disks, k, selectors)
+                .ok_or_else(|| format!("the solution has fewer than {k} moves"))?;
+            let last = nth_move(disks, k, selectors);
+            if let Some((source, target)) = last {
+                println!("Move {k}: {source}{target}");
+            }
+            print_towers(&config, last, &towers);
+        }
+        Mode::Solve if config.load.is_some() => {
+            let path = config.load.as_deref().expect("guarded by is_some");
+            solve_saved(path, &config)?;
+        }
+        Mode::Solve => {
+            let mut viewer = Viewer::new(&config)?;
+            viewer.show(None, &towers);
+            let mut made = Vec::new();
+            let on_move = &mut |last, towers: &TowerSet| {
+                viewer.show(Some(last), towers);
+                if config.write_moves.is_some() || config.svg.is_some() || config.gif.is_some() {
+                    made.push(last);
+                }
+            };
+            let towers = if let Some(variant) = config.variant {
+                play(towers, variant.moves(disks, selectors), on_move)?
+            } else if config.rules != Rules::Classic {
+                let moves =
+                    (config.rules.moves(disks, config.source, config.target)).ok_or_else(|| {
+                        format!(
+                            "the {} rules never let a tower reach {} from {}",
+                            config.rules, config.target, config.source
+                        )
+                    })?;
+                play_with(&config.rules, towers, moves, on_move)?
+            } else if config.pegs != 3 {
+                FrameStewart::new(config.pegs, disks).solve(
+                    towers,
+                    disks,
+                    &config.all_selectors(),
+                    on_move,
+                )?
+            } else {
+                match config.solver {
+                    Solver::Recursive => solve(towers, disks, selectors, on_move)?,
+                    Solver::Iterative => play(towers, moves_iterative(disks, selectors), on_move)?,
+                    Solver::Bitwise => play(towers, moves_bitwise(disks, selectors), on_move)?,
+                    Solver::EmulateRecursion => {
+                        play(towers, moves_emulate_recursion(disks, selectors), on_move)?
+                    }
+                }
+            };
+            viewer.finish(&towers)?;
+            if let Some(path) = &config.svg {
+                export_svg(path, &config, &config.towers(), &made)?;
+            }
+            if let Some(path) = &config.gif {
+                export_gif(path, &config, &config.towers(), &made)?;
+            }
+            if let Some(path) = &config.write_moves {
+                let mut recorder = MoveRecorder::new(io::BufWriter::new(File::create(path)?));
+                recorder.comment(&format!(
+                    "{disks} disks from tower {} to tower {}",
+                    config.source, config.target
+                ))?;
+                for next in made {
+                    recorder.record(next)?;
+                }
+                recorder.finish()?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const A: TowerSelector = TowerSelector::A;
+    const B: TowerSelector = TowerSelector::B;
+    const C: TowerSelector = TowerSelector::C;
+    fn parse_args(args: &[&str]) -> Result<Config, UsageError> {
+        Config::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args(&[]), Ok(Config::default()));
+        assert_eq!(
+            parse_args(&["-n", "3", "--pegs", "4", "-S", "C", "-t", "D", "-b", "A"]),
+            Ok(Config {
+                disks: 3,
+                pegs: 4,
+                source: C,
+                target: TowerSelector(3),
+                buffer: A,
+                ..Config::default()
+            })
+        );
+        assert_eq!(
+            parse_args(&["-d", "20", "-o", "animate", "-q", "-s", "bitwise"]),
+            Ok(Config {
+                delay: Duration::from_millis(20),
+                output: Output::Animate,
+                quiet: true,
+                solver: Solver::Bitwise,
+                ..Config::default()
+            })
+        );
+        assert_eq!(
+            parse_args(&["--interactive"]).map(|config| config.mode),
+            Ok(Mode::Interact)
+        );
+        assert_eq!(
+            parse_args(&["-k", "7", "--after", "7"]).map(|config| config.mode),
+            Ok(Mode::After(7))
+        );
+        let config = parse_args(&["--replay", "game.txt"]).unwrap();
+        assert_eq!(config.mode, Mode::Replay(PathBuf::from("game.txt")));
+        assert_eq!(config.output, Output::Animate);
+        let config = parse_args(&["-r", "game.txt", "-o", "towers"]).unwrap();
+        assert_eq!(config.output, Output::Towers);
+        assert_eq!(
+            parse_args(&["--style", "json"]).map(|config| config.style),
+            Ok(Style::Json)
+        );
+        let config = parse_args(&[
+            "-g",
+            "out.svg",
+            "--svg-size",
+            "800x300",
+            "--svg-colours",
+            "black,grey,red,blue",
+        ])
+        .unwrap();
+        assert_eq!(
+            config.svg_style,
+            SvgStyle {
+                width: 800,
+                height: 300,
+                background: "black".to_string(),
+                pegs: "grey".to_string(),
+                disks: vec!["red".to_string(), "blue".to_string()],
+                ..SvgStyle::default()
+            }
+        );
+        let config = parse_args(&["-t", "C"]).unwrap();
+        assert_eq!((config.source, config.target, config.buffer), (A, C, B));
+        assert_eq!(
+            parse_args(&["-u", "BA,CB", "-c", "moves.txt"]).map(|config| config.rules),
+            Ok(Rules::Digraph(vec![(B, A), (C, B)]))
+        );
+        assert_eq!(
+            parse_args(&["--bot", "-w", "game.txt"]).map(|config| config.mode),
+            Ok(Mode::Bot(None))
+        );
+        assert_eq!(
+            parse_args(&["--listen", "7070"]).map(|config| config.mode),
+            Ok(Mode::Bot(Some(7070)))
+        );
+        let config = parse_args(&["--puzzle", "--seed", "42"]).unwrap();
+        assert_eq!((config.mode, config.seed), (Mode::Puzzle, Some(42)));
+        assert_eq!(
+            parse_args(&["--drills", "5"]).map(|config| config.mode),
+            Ok(Mode::Drills(5))
+        );
+        let config = parse_args(&["--trace", "-n", "3", "--dot", "calls.dot"]).unwrap();
+        assert_eq!(config.mode, Mode::Trace);
+        assert_eq!(config.dot, Some(PathBuf::from("calls.dot")));
+        assert_eq!(
+            parse_args(&["-V", "ordered-copies:2"]).map(|config| config.variant),
+            Ok(Some(Variant::OrderedCopies(2)))
+        );
+        assert_eq!(
+            parse_args(&["-u", "magnetic", "-n", "4"]).map(|config| config.rules),
+            Ok(Rules::Magnetic)
+        );
+        let selectors = parse_args(&["-p", "5", "-t", "E"]).unwrap().all_selectors();
+        assert_eq!(selectors, [A, TowerSelector(4), B, C, TowerSelector(3)]);
+    }
+
+    #[test]
+    fn test_parse_bad_args() {
+        for (args, want) in [
+            (&["--frobnicate"][..], "--frobnicate: unknown flag"),
+            (&["-n"], "-n: missing value"),
+            (&["-n", "many"], "-n: invalid digit found in string"),
+            (&["-o", "smoke"], "-o: smoke: bad output mode"),
+            (&["-s", "psychic"], "-s: psychic: bad solver"),
+            (&["-y", "fancy"], "-y: fancy: bad style"),
+            (&["--svg-size", "640"], "--svg-size: 640: bad size"),
+            (&["--svg-frame", "middle"], "--svg-frame: middle: bad frame"),
+            (
+                &["--svg-colours", "white,black"],
+                "--svg-colours: expected background, pegs, and at least one disk colour",
+            ),
+            (
+                &["-i", "-g", "out.svg"],
+                "only solutions and replays can be drawn",
+            ),
+            (
+                &["-k", "1", "-G", "out.gif"],
+                "only solutions and replays can be drawn",
+            ),
+            (&["-t", "b"], "-t: b: bad tower selector"),
+            (&["-i", "-k", "3"], "-k: conflicts with an earlier flag"),
+            (&["-p", "2"], "peg count should be from 3 to 26: 2"),
+            (&["-t", "D"], "there is no tower D among 3 pegs"),
+            (
+                &["-t", "C", "-b", "C"],
+                "source, target, and buffer should differ: A, C, C",
+            ),
+            (
+                &["-p", "4", "-k", "1"],
+                "skipping moves requires three pegs",
+            ),
+            (&["-p", "4", "-x"], "exploring requires three pegs"),
+            (&["-n", "9", "-x"], "exploring supports at most 8 disks"),
+            (
+                &["-n", "65", "-P"],
+                "random positions support at most 64 disks",
+            ),
+            (
+                &["--drills", "1", "-n", "100", "--seed", "1"],
+                "random positions support at most 64 disks",
+            ),
+            (
+                &["-n", "200", "-r", "moves.txt", "-G", "out.gif"],
+                "GIFs can show at most 152 disks",
+            ),
+            (
+                &["-n", "60", "-V", "copies:3", "-G", "out.gif"],
+                "GIFs can show at most 152 disks",
+            ),
+            (
+                &["--dot", "a.dot"],
+                "only explored graphs and traces can be written",
+            ),
+            (&["-p", "4", "-T"], "tracing requires three pegs"),
+            (&["-n", "11", "-T"], "tracing supports at most 10 disks"),
+            (
+                &["-T", "-s", "bitwise"],
+                "only the recursive solver can be traced",
+            ),
+            (&["-T", "-i"], "-i: conflicts with an earlier flag"),
+            (
+                &["-p", "4", "-s", "iterative"],
+                "the Iterative solver requires three pegs",
+            ),
+            (
+                &["-n", "65", "-s", "bitwise"],
+                "the bitwise solver supports at most 64 disks",
+            ),
+            (
+                &["-c", "a.txt", "-r", "b.txt"],
+                "-r: conflicts with an earlier flag",
+            ),
+            (
+                &["-c", "a.txt", "-l", "b.txt"],
+                "only solving and interactive play can start from a saved game",
+            ),
+            (
+                &["-i", "-w", "moves.txt"],
+                "only the moves of the solver or a bot can be written",
+            ),
+            (
+                &["--listen", "http"],
+                "--listen: invalid digit found in string",
+            ),
+            (&["--bot", "-p", "4"], "bot games require three pegs"),
+            (&["-P", "-p", "4"], "random positions require three pegs"),
+            (&["-i", "--seed", "1"], "only puzzles and drills use a seed"),
+            (&["--seed", "-1"], "--seed: invalid digit found in string"),
+            (
+                &["-P", "-l", "game.txt"],
+                "only solving and interactive play can start from a saved game",
+            ),
+            (
+                &["--bot", "-u", "cyclic"],
+                "the cyclic rules apply only to solving, replaying, and checking",
+            ),
+            (
+                &["-k", "3", "-R", "out.cast"],
+                "only solutions and replays can be recorded",
+            ),
+            (&["-u", "AC,CC"], "-u: AC,CC: bad rules"),
+            (
+                &["-u", "cyclic", "-p", "4"],
+                "the cyclic rules require three pegs",
+            ),
+            (
+                &["-u", "AB,BC", "-i"],
+                "the AB,BC rules apply only to solving, replaying, and checking",
+            ),
+            (
+                &["-u", "adjacent", "-s", "bitwise"],
+                "the Bitwise solver requires the classic rules",
+            ),
+            (&["-V", "tricolor"], "-V: tricolor: bad variant"),
+            (
+                &["-V", "bicolor", "-p", "4"],
+                "the bicolor variant requires three pegs",
+            ),
+            (
+                &["-V", "copies:2", "-i"],
+                "the copies:2 variant applies only to solving",
+            ),
+            (
+                &["-V", "copies:2", "-u", "cyclic"],
+                "the copies:2 variant requires the classic rules and solver",
+            ),
+            (
+                &["-V", "bicolor", "-n", "6"],
+                "searching for solutions supports at most 5 disk sizes",
+            ),
+            (
+                &["-u", "magnetic", "-n", "6"],
+                "searching for solutions supports at most 5 disk sizes",
+            ),
+        ] {
+            assert_eq!(parse_args(args), Err(UsageError(want.to_string())));
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! A line-oriented protocol for playing the puzzle with a program, or bot,
+//! rather than a person, over standard input and output or a TCP connection.
+//! The referee announces the target tower, then sends the towers, and the bot
+//! replies with a move in the notation of the `notation` module.  The referee
+//! answers `ok`, `illegal` followed by the reason, or `won` followed by the
+//! number of moves, and sends the towers again until the game is won:
+//!
+//! ```text
+//! > hanoi target B
+//! > state A[2 1] B[] C[]
+//! < AC
+//! > ok
+//! > state A[2] B[] C[1]
+//! < CC
+//! > illegal tower C cannot be both source and target
+//! > state A[2] B[] C[1]
+//! < AB
+//! > ok
+//! > state A[] B[2] C[1]
+//! < CB
+//! > won 3
+//! ```
+//!
+//! The bot may reply `quit` to give up, and blank lines are ignored.  A bot
+//! that takes more than ten turns for each move of the optimal solution
+//! loses, and is told `lost` before the referee stops reading.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use super::notation;
+use super::render::Render;
+use super::Session;
+
+/// The number of turns a bot may take for each move of the optimal solution,
+/// beyond which it loses.
+const TURNS_PER_MOVE: u64 = 10;
+
+/// The result of a game with a bot.
+pub struct Game {
+    /// The game as it stood when it ended.
+    pub session: Session,
+    /// The number of replies that were not legal moves.
+    pub illegal: u64,
+}
+
+impl Game {
+    /// Returns a score out of 100: the optimal number of moves as a
+    /// percentage of the turns taken, counting illegal moves, or 0 if the
+    /// game was not won.
+    pub fn score(&self) -> u64 {
+        self.session.score_with_illegal(self.illegal)
+    }
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.session.summary())?;
+        writeln!(f, "Illegal moves: {}", self.illegal)?;
+        write!(f, "Score: {}/100", self.score())
+    }
+}
+
+/// Writes a line to the bot, flushing it so the bot sees it at once.
+fn send(output: &mut impl Write, line: &str) -> io::Result<()> {
+    writeln!(output, "{line}")?;
+    output.flush()
+}
+
+/// Plays a game with a bot, reading its replies from `input` and writing to
+/// `output`, with the towers drawn by a single-line renderer.  The game ends
+/// when it is won or lost, or when the bot quits or stops replying.
+pub fn referee(
+    mut session: Session,
+    renderer: &dyn Render,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> Result<Game, Box<dyn Error>> {
+    let limit = TURNS_PER_MOVE * session.optimal.unwrap_or_default().max(1);
+    let mut illegal = 0;
+    let mut lines = input.lines();
+    send(&mut output, &format!("hanoi target {}", session.target))?;
+    while !session.is_won() {
+        if session.history.len() as u64 + illegal >= limit {
+            send(&mut output, "lost")?;
+            break;
+        }
+        let mut state = b"state ".to_vec();
+        renderer.render(&mut state, &session.towers, None)?;
+        output.write_all(&state)?;
+        output.flush()?;
+        let line = loop {
+            match lines.next().transpose()? {
+                Some(line) if line.trim().is_empty() => continue,
+                line => break line,
+            }
+        };
+        let Some(line) = line.filter(|line| line.trim() != "quit") else {
+            break;
+        };
+        let next = match notation::parse_moves(&line).as_deref() {
+            Ok(&[next]) => next,
+            _ => {
+                illegal += 1;
+                send(&mut output, &format!("illegal {}: bad move", line.trim()))?;
+                continue;
+            }
+        };
+        match session.play(next.0, next.1) {
+            Ok(()) if session.is_won() => {
+                send(&mut output, &format!("won {}", session.history.len()))?
+            }
+            Ok(()) => send(&mut output, "ok")?,
+            Err(err) => {
+                illegal += 1;
+                send(&mut output, &format!("illegal {err}"))?;
+            }
+        }
+    }
+    Ok(Game { session, illegal })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hanoi::render::{Compact, Json};
+    use crate::hanoi::{first_gather_move, TowerSelector, TowerSet};
+    use std::io::{BufReader, Cursor};
+    use std::net::{Ipv4Addr, TcpListener, TcpStream};
+    use std::thread;
+
+    const A: TowerSelector = TowerSelector::A;
+    const B: TowerSelector = TowerSelector::B;
+
+    /// Parses towers drawn by the `Compact` renderer, such as
+    /// `A[2] B[] C[1]`, into the tower of each disk, smallest first.
+    fn parse_state(s: &str) -> Vec<TowerSelector> {
+        let mut positions = Vec::new();
+        for tower in s
+            .split(']')
+            .map(str::trim)
+            .filter(|tower| !tower.is_empty())
+        {
+            let (label, radii) = tower.split_once('[').unwrap();
+            for radius in radii.split_whitespace() {
+                let radius: usize = radius.parse().unwrap();
+                if positions.len() < radius {
+                    positions.resize(radius, A);
+                }
+                positions[radius - 1] = label.parse().unwrap();
+            }
+        }
+        positions
+    }
+
+    /// Plays the optimal moves until told the game is won.
+    fn optimal_bot(input: impl BufRead, mut output: impl Write) {
+        let mut target = None;
+        for line in input.lines() {
+            let line = line.unwrap();
+            if let Some(label) = line.strip_prefix("hanoi target ") {
+                target = Some(label.parse().unwrap());
+            } else if let Some(state) = line.strip_prefix("state ") {
+                let positions = parse_state(state);
+                let (source, target) = first_gather_move(&positions, target.unwrap()).unwrap();
+                writeln!(output, "{source}{target}").unwrap();
+            } else if line.starts_with("won") {
+                return;
+            } else {
+                assert_eq!(line, "ok");
+            }
+        }
+    }
+
+    #[test]
+    fn test_optimal_bot() {
+        for disks in 0..=6 {
+            let session = Session::new(TowerSet::with_pegs(3, disks), B);
+            let (bot_input, to_bot) = io::pipe().unwrap();
+            let (from_bot, bot_output) = io::pipe().unwrap();
+            let bot = thread::spawn(move || optimal_bot(BufReader::new(bot_input), bot_output));
+            let game = referee(session, &Compact, BufReader::new(from_bot), to_bot).unwrap();
+            bot.join().unwrap();
+            assert!(game.session.is_won());
+            assert_eq!(game.session.history.len(), (1 << disks) - 1);
+            assert_eq!(game.illegal, 0);
+            assert_eq!(game.score(), 100);
+        }
+    }
+
+    #[test]
+    fn test_optimal_bot_over_tcp() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let address = listener.local_addr().unwrap();
+        let bot = thread::spawn(move || {
+            let stream = TcpStream::connect(address).unwrap();
+            optimal_bot(BufReader::new(stream.try_clone().unwrap()), stream);
+        });
+        let (stream, _) = listener.accept().unwrap();
+        let session = Session::new(TowerSet::with_pegs(3, 4), B);
+        let input = BufReader::new(stream.try_clone().unwrap());
+        let game = referee(session, &Compact, input, stream).unwrap();
+        bot.join().unwrap();
+        assert_eq!(game.session.history.len(), 15);
+        assert_eq!(game.score(), 100);
+    }
+
+    #[test]
+    fn test_transcript() {
+        let session = Session::new(TowerSet::with_pegs(3, 2), B);
+        let replies = "AC\n\nCC\nA\nAB\nCB\n";
+        let mut output = Vec::new();
+        let game = referee(session, &Compact, Cursor::new(replies), &mut output).unwrap();
+        let want = [
+            "hanoi target B",
+            "state A[2 1] B[] C[]",
+            "ok",
+            "state A[2] B[] C[1]",
+            "illegal tower C cannot be both source and target",
+            "state A[2] B[] C[1]",
+            "illegal A: bad move",
+            "state A[2] B[] C[1]",
+            "ok",
+            "state A[] B[2] C[1]",
+            "won 3",
+        ];
+        assert_eq!(String::from_utf8(output).unwrap(), want.join("\n") + "\n");
+        assert_eq!(game.illegal, 2);
+        assert_eq!(game.score(), 60);
+        assert_eq!(
+            game.to_string(),
+            "Solved in 3 moves, which is optimal!\nIllegal moves: 2\nScore: 60/100"
+        );
+    }
+
+    #[test]
+    fn test_quit_and_lose() {
+        let session = Session::new(TowerSet::with_pegs(3, 1), B);
+        let mut output = Vec::new();
+        let game = referee(session, &Json, Cursor::new("quit\n"), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "hanoi target B\nstate {\"move\":null,\"towers\":{\"A\":[1],\"B\":[],\"C\":[]}}\n"
+        );
+        assert!(!game.session.is_won());
+        assert_eq!(game.score(), 0);
+
+        // A bot that moves the disk back and forth never wins.
+        let session = Session::new(TowerSet::with_pegs(3, 1), B);
+        let replies = "AC\nCA\n".repeat(10);
+        let mut output = Vec::new();
+        let game = referee(session, &Compact, Cursor::new(replies), &mut output).unwrap();
+        assert_eq!(game.session.history.len(), 10);
+        assert!(String::from_utf8(output).unwrap().ends_with("ok\nlost\n"));
+        assert_eq!(game.score(), 0);
+    }
+}
@@ -0,0 +1,142 @@
+//! Recordings in the asciinema v2 format, which can be replayed offline in a
+//! terminal with `asciinema play` or embedded in a web page.  A recording is
+//! a line of JSON describing the terminal, followed by a line of JSON for each
+//! chunk of output, giving the time it was written:
+//!
+//! ```text
+//! {"version": 2, "width": 23, "height": 5}
+//! [0.000000, "o", "\u001b[2J\u001b[H..."]
+//! [0.500000, "o", "\u001b[2J\u001b[H..."]
+//! ```
+//!
+//! See https://docs.asciinema.org/manual/asciicast/v2/ for details.
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::time::Duration;
+
+/// Quotes a string for JSON.
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => {
+                write!(quoted, "\\u{:04x}", c as u32).expect("writing to a string cannot fail")
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Returns the number of columns a line takes up in a terminal, not counting
+/// ANSI escape sequences such as colour changes.
+fn columns(line: &str) -> usize {
+    let mut count = 0;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip the sequence up to and including its final letter.
+            chars.find(char::is_ascii_alphabetic);
+        } else {
+            count += 1;
+        }
+    }
+    count
+}
+
+/// Writes frames to a recording, as though each was shown for a fixed delay.
+/// Nothing is written until the first frame, which sets the size of the
+/// terminal.
+pub struct CastRecorder<W: Write> {
+    out: W,
+    delay: Duration,
+    time: Duration, // when the next frame is shown
+    frames: usize,
+}
+
+impl<W: Write> CastRecorder<W> {
+    /// Starts a recording in `out`, with the specified pause between frames.
+    pub fn new(out: W, delay: Duration) -> CastRecorder<W> {
+        CastRecorder {
+            out,
+            delay,
+            time: Duration::ZERO,
+            frames: 0,
+        }
+    }
+
+    /// Records a frame, given the text written to show it.  Terminals in raw
+    /// mode need a carriage return before each line feed, so one is added.
+    pub fn frame(&mut self, text: &str) -> io::Result<()> {
+        if self.frames == 0 {
+            let width = text.lines().map(columns).max().unwrap_or_default();
+            // Leave a line for the cursor after the frame.
+            let height = text.lines().count() + 1;
+            writeln!(
+                self.out,
+                "{{\"version\": 2, \"width\": {width}, \"height\": {height}}}"
+            )?;
+        }
+        writeln!(
+            self.out,
+            "[{:.6}, \"o\", {}]",
+            self.time.as_secs_f64(),
+            quote(&text.replace('\n', "\r\n"))
+        )?;
+        self.time += self.delay;
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Returns the number of frames recorded so far.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Flushes the recording, and returns where it was written.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("plain"), "\"plain\"");
+        assert_eq!(quote("say \"hi\"\\\r\n"), "\"say \\\"hi\\\"\\\\\\r\\n\"");
+        assert_eq!(quote("\x1b[2J"), "\"\\u001b[2J\"");
+    }
+
+    #[test]
+    fn test_columns() {
+        assert_eq!(columns("  ||  "), 6);
+        assert_eq!(columns("\x1b[2J\x1b[H ┃ \x1b[31m███\x1b[0m"), 6);
+    }
+
+    #[test]
+    fn test_recorder() {
+        let mut recorder = CastRecorder::new(Vec::new(), Duration::from_millis(250));
+        recorder.frame("\x1b[2J\x1b[Hab\ncd\n").unwrap();
+        recorder.frame("\x1b[2J\x1b[Hefg\n").unwrap();
+        assert_eq!(recorder.frames(), 2);
+        let text = String::from_utf8(recorder.finish().unwrap()).unwrap();
+        let want = [
+            "{\"version\": 2, \"width\": 2, \"height\": 3}",
+            "[0.000000, \"o\", \"\\u001b[2J\\u001b[Hab\\r\\ncd\\r\\n\"]",
+            "[0.250000, \"o\", \"\\u001b[2J\\u001b[Hefg\\r\\n\"]",
+        ];
+        assert_eq!(text, want.join("\n") + "\n");
+    }
+}
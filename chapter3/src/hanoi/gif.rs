@@ -0,0 +1,499 @@
+//! Animated GIFs of the towers, written without any outside library.  Each
+//! frame is drawn with one palette entry per disk size, or per colour if the
+//! disks have several, compressed with the
+//! variable-width LZW scheme that GIF requires, and shown for a fixed delay,
+//! with the whole animation repeating forever.
+//!
+//! See https://www.w3.org/Graphics/GIF/spec-gif89a.txt for the format.
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+use super::TowerSet;
+
+/// The palette entries of the background and the pegs.  The disks follow.
+const BACKGROUND: u8 = 0;
+const PEGS: u8 = 1;
+
+/// The most entries a GIF palette may have.
+const MAX_COLOURS: usize = 256;
+
+/// The longest code, in bits, that LZW compression may use.
+const MAX_CODE_BITS: u32 = 12;
+
+/// Returns the palette entry of a disk's shade, as given by `Disk::shade`.
+/// Shades have their own entries, unless there are more shades than entries.
+pub fn disk_colour(shade: usize) -> u8 {
+    (2 + shade % (MAX_COLOURS - 2)) as u8
+}
+
+/// Returns a palette with an entry for the background, the pegs, and each
+/// disk, with the hues of the disks spread around the colour wheel.
+pub fn palette(disk_count: usize) -> Vec<[u8; 3]> {
+    let mut palette = vec![[255, 255, 255], [85, 85, 85]];
+    for index in 0..disk_count.min(MAX_COLOURS - 2) {
+        // Successive multiples of the golden ratio are spread evenly.
+        let hue = (index as f64 * 0.618_034).fract() * 6.0;
+        let (value, saturation) = (230.0, 0.7);
+        let channel = |offset: f64| {
+            let distance = ((hue + offset) % 6.0 - 3.0).abs();
+            let weight = (distance - 1.0).clamp(0.0, 1.0);
+            (value * (1.0 - saturation * (1.0 - weight))) as u8
+        };
+        palette.push([channel(0.0), channel(4.0), channel(2.0)]);
+    }
+    palette
+}
+
+/// Returns the height of the margins above the pegs and below the base, and
+/// the row on which the base starts, for a frame of the specified height.
+fn layout(height: usize) -> (usize, usize) {
+    let margin = (height / 20).max(1);
+    (margin, height.saturating_sub(2 * margin))
+}
+
+/// Returns the most disks that fit in a frame of the specified height, each at
+/// least one pixel tall.
+pub fn max_disks(height: usize) -> usize {
+    let (margin, base) = layout(height);
+    base.saturating_sub(margin).saturating_sub(1)
+}
+
+/// Draws the towers as rows of palette entries, top row first.  Disks that
+/// would rise above the frame, when there are more than `max_disks`, are left
+/// out.
+pub fn rasterise(towers: &TowerSet, width: usize, height: usize) -> Vec<u8> {
+    let mut pixels = vec![BACKGROUND; width * height];
+    let mut fill = |x: usize, y: usize, w: usize, h: usize, colour: u8| {
+        for row in pixels.chunks_mut(width).skip(y).take(h) {
+            row[x..x + w].fill(colour);
+        }
+    };
+    let disk_count = towers.disks().count();
+    let by_colour = towers.is_coloured();
+    let column = width / towers.towers.len();
+    let (margin, base) = layout(height);
+    let disk_height = (base.saturating_sub(margin) / (disk_count + 1))
+        .min(column / 6)
+        .max(1);
+    let (smallest, largest) = ((column / 5).max(1), column * 9 / 10);
+    let largest_radius = towers
+        .disks()
+        .map(|disk| disk.radius)
+        .max()
+        .unwrap_or_default();
+    let disk_width = |radius: usize| match largest_radius {
+        0 | 1 => largest,
+        n => smallest + (largest - smallest) * (radius - 1) / (n - 1),
+    };
+
+    fill(0, base, width, margin, PEGS);
+    let peg_width = (disk_height / 3).max(1);
+    let peg_height = (disk_count * disk_height + disk_height / 2).min(base);
+    for (index, tower) in towers.into_iter().enumerate() {
+        let centre = column * index + column / 2;
+        fill(
+            centre - peg_width / 2,
+            base - peg_height,
+            peg_width,
+            peg_height,
+            PEGS,
+        );
+        for (below, disk) in tower.disks.iter().enumerate() {
+            let w = disk_width(disk.radius);
+            let Some(y) = base.checked_sub((below + 1) * disk_height) else {
+                break;
+            };
+            let colour = disk_colour(disk.shade(by_colour));
+            fill(centre - w / 2, y, w, disk_height, colour);
+        }
+    }
+    pixels
+}
+
+/// Packs codes of varying widths into bytes, least significant bit first.
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, width: u32) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += width;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Compresses pixels with LZW, starting each code with the specified number
+/// of bits plus one, and clearing the table whenever it fills up.
+fn lzw_encode(pixels: &[u8], min_code_bits: u32) -> Vec<u8> {
+    use std::collections::HashMap;
+
+    let clear = 1u16 << min_code_bits;
+    let end = clear + 1;
+    let mut out = BitWriter {
+        bytes: Vec::new(),
+        buffer: 0,
+        bits: 0,
+    };
+    // Each table entry is a known string extended by one pixel.
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end + 1;
+    let mut width = min_code_bits + 1;
+    out.write(clear, width);
+    let mut pixels = pixels.iter();
+    let Some(&first) = pixels.next() else {
+        out.write(end, width);
+        return out.finish();
+    };
+    let mut current = first as u16;
+    for &pixel in pixels {
+        if let Some(&code) = table.get(&(current, pixel)) {
+            current = code;
+            continue;
+        }
+        out.write(current, width);
+        if next_code < 1 << MAX_CODE_BITS {
+            table.insert((current, pixel), next_code);
+            // Decoders widen their codes as soon as the table reaches the
+            // next power of two, so widen in step.
+            if next_code == 1 << width && width < MAX_CODE_BITS {
+                width += 1;
+            }
+            next_code += 1;
+        } else {
+            out.write(clear, width);
+            table.clear();
+            next_code = end + 1;
+            width = min_code_bits + 1;
+        }
+        current = pixel as u16;
+    }
+    out.write(current, width);
+    out.write(end, width);
+    out.finish()
+}
+
+/// Writes an animated GIF one frame at a time.
+pub struct GifEncoder<W: Write> {
+    out: W,
+    width: u16,
+    height: u16,
+    colour_bits: u32, // the palette has 2^colour_bits entries
+}
+
+impl<W: Write> GifEncoder<W> {
+    /// Writes the header, palette, and an instruction to loop forever.
+    pub fn new(mut out: W, width: u16, height: u16, palette: &[[u8; 3]]) -> io::Result<Self> {
+        assert!(palette.len() <= MAX_COLOURS, "too many colours");
+        // Palettes have between 2^1 and 2^8 entries, and LZW codes start at
+        // least three bits wide, so use no fewer than four colours.
+        let colour_bits = palette.len().max(4).next_power_of_two().trailing_zeros();
+        out.write_all(b"GIF89a")?;
+        out.write_all(&width.to_le_bytes())?;
+        out.write_all(&height.to_le_bytes())?;
+        // A global palette, with 8 bits of colour resolution.
+        out.write_all(&[0xf0 | (colour_bits - 1) as u8, BACKGROUND, 0])?;
+        for entry in 0..1 << colour_bits {
+            out.write_all(palette.get(entry).unwrap_or(&[0, 0, 0]))?;
+        }
+        // The Netscape extension, with a repeat count of zero for forever.
+        out.write_all(&[0x21, 0xff, 11])?;
+        out.write_all(b"NETSCAPE2.0")?;
+        out.write_all(&[3, 1, 0, 0, 0])?;
+        Ok(GifEncoder {
+            out,
+            width,
+            height,
+            colour_bits,
+        })
+    }
+
+    /// Writes a frame of palette entries, top row first, to be shown for the
+    /// specified delay (in hundredths of a second, at most ten minutes).
+    pub fn frame(&mut self, pixels: &[u8], delay: Duration) -> io::Result<()> {
+        assert_eq!(pixels.len(), self.width as usize * self.height as usize);
+        let delay = (delay.as_millis() / 10).min(u16::MAX as u128) as u16;
+        // A graphic control extension, giving the delay.
+        self.out.write_all(&[0x21, 0xf9, 4, 0])?;
+        self.out.write_all(&delay.to_le_bytes())?;
+        self.out.write_all(&[0, 0])?;
+        // An image descriptor covering the whole screen, with no palette of
+        // its own.
+        self.out.write_all(&[0x2c, 0, 0, 0, 0])?;
+        self.out.write_all(&self.width.to_le_bytes())?;
+        self.out.write_all(&self.height.to_le_bytes())?;
+        self.out.write_all(&[0])?;
+        // The compressed pixels, in blocks of up to 255 bytes.
+        let min_code_bits = self.colour_bits.max(2);
+        self.out.write_all(&[min_code_bits as u8])?;
+        for block in lzw_encode(pixels, min_code_bits).chunks(255) {
+            self.out.write_all(&[block.len() as u8])?;
+            self.out.write_all(block)?;
+        }
+        self.out.write_all(&[0])
+    }
+
+    /// Ends the animation, and returns where it was written.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.write_all(&[0x3b])?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hanoi::{Tower, TowerSelector};
+
+    const A: TowerSelector = TowerSelector::A;
+    const B: TowerSelector = TowerSelector::B;
+    const C: TowerSelector = TowerSelector::C;
+
+    /// Reads codes of varying widths from bytes, least significant bit
+    /// first.
+    struct BitReader<'a> {
+        bytes: &'a [u8],
+        position: usize, // in bits
+    }
+
+    impl BitReader<'_> {
+        fn read(&mut self, width: u32) -> u16 {
+            let mut code = 0;
+            for bit in 0..width {
+                let byte = self.bytes[self.position / 8];
+                code |= ((byte >> (self.position % 8)) as u16 & 1) << bit;
+                self.position += 1;
+            }
+            code
+        }
+    }
+
+    fn lzw_decode(bytes: &[u8], min_code_bits: u32) -> Vec<u8> {
+        let clear = 1u16 << min_code_bits;
+        let end = clear + 1;
+        let mut input = BitReader { bytes, position: 0 };
+        let mut out = Vec::new();
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut width = min_code_bits + 1;
+        let mut previous: Option<Vec<u8>> = None;
+        loop {
+            let code = input.read(width);
+            if code == clear {
+                table = (0..clear).map(|pixel| vec![pixel as u8]).collect();
+                table.extend([vec![], vec![]]); // clear and end
+                width = min_code_bits + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return out;
+            }
+            let entry = match (table.get(code as usize), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => {
+                    assert_eq!(code as usize, table.len(), "code out of sequence");
+                    let mut entry = previous.clone();
+                    entry.push(previous[0]);
+                    entry
+                }
+                (None, None) => panic!("unknown first code {code}"),
+            };
+            out.extend(&entry);
+            if let Some(mut previous) = previous.take() {
+                if table.len() < 1 << MAX_CODE_BITS {
+                    previous.push(entry[0]);
+                    table.push(previous);
+                }
+            }
+            if table.len() == 1 << width && width < MAX_CODE_BITS {
+                width += 1;
+            }
+            previous = Some(entry);
+        }
+    }
+
+    /// The parts of a decoded GIF that the tests check.
+    struct Decoded {
+        width: usize,
+        height: usize,
+        palette_len: usize,
+        loops: bool,
+        frames: Vec<(u16, Vec<u8>)>, // delay and pixels
+    }
+
+    /// Returns the data of the sub-blocks at the start of the input, and
+    /// whatever follows them.
+    fn read_blocks(mut rest: &[u8]) -> (Vec<u8>, &[u8]) {
+        let mut data = Vec::new();
+        loop {
+            let (&len, after) = rest.split_first().expect("block size");
+            rest = &after[len as usize..];
+            if len == 0 {
+                return (data, rest);
+            }
+            data.extend(&after[..len as usize]);
+        }
+    }
+
+    fn decode(gif: &[u8]) -> Decoded {
+        assert_eq!(&gif[..6], b"GIF89a");
+        let width = u16::from_le_bytes([gif[6], gif[7]]) as usize;
+        let height = u16::from_le_bytes([gif[8], gif[9]]) as usize;
+        assert_ne!(gif[10] & 0x80, 0, "global palette");
+        let palette_len = 2 << (gif[10] & 7);
+        let mut rest = &gif[13 + 3 * palette_len..];
+        let mut decoded = Decoded {
+            width,
+            height,
+            palette_len,
+            loops: false,
+            frames: Vec::new(),
+        };
+        let mut delay = 0;
+        loop {
+            match rest {
+                [0x3b] => return decoded,
+                [0x21, 0xff, 11, after @ ..] => {
+                    assert_eq!(&after[..11], b"NETSCAPE2.0");
+                    let (data, after) = read_blocks(&after[11..]);
+                    decoded.loops = data == [1, 0, 0];
+                    rest = after;
+                }
+                [0x21, 0xf9, 4, _, low, high, _, 0, after @ ..] => {
+                    delay = u16::from_le_bytes([*low, *high]);
+                    rest = after;
+                }
+                [0x2c, descriptor @ ..] => {
+                    assert_eq!(&descriptor[..4], [0, 0, 0, 0]);
+                    assert_eq!(descriptor[8], 0, "no local palette");
+                    let min_code_bits = descriptor[9] as u32;
+                    let (data, after) = read_blocks(&descriptor[10..]);
+                    let pixels = lzw_decode(&data, min_code_bits);
+                    assert_eq!(pixels.len(), width * height);
+                    decoded.frames.push((delay, pixels));
+                    rest = after;
+                }
+                _ => panic!("unexpected block {:x?}", &rest[..rest.len().min(4)]),
+            }
+        }
+    }
+
+    #[test]
+    fn test_lzw() {
+        let long: Vec<u8> = (0..20_000u32).map(|i| ((i * i) % 7) as u8).collect();
+        for (pixels, bits) in [
+            (vec![], 2),
+            (vec![3], 2),
+            (vec![0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0], 2),
+            (long, 3),
+            ((0..=255).cycle().take(10_000).collect(), 8),
+        ] {
+            assert_eq!(lzw_decode(&lzw_encode(&pixels, bits), bits), pixels);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let moves = [(A, C), (A, B), (C, B)];
+        let (width, height) = (120, 60);
+        let mut towers = TowerSet::with_pegs(3, 2);
+        let mut frames = vec![towers.clone()];
+        for (source, target) in moves {
+            towers.move_disk(source, target).unwrap();
+            frames.push(towers.clone());
+        }
+
+        let mut encoder = GifEncoder::new(Vec::new(), width, height, &palette(2)).unwrap();
+        for towers in &frames {
+            let pixels = rasterise(towers, width as usize, height as usize);
+            encoder.frame(&pixels, Duration::from_millis(250)).unwrap();
+        }
+        let decoded = decode(&encoder.finish().unwrap());
+        assert_eq!((decoded.width, decoded.height), (120, 60));
+        assert_eq!(decoded.palette_len, 4);
+        assert!(decoded.loops);
+        assert_eq!(decoded.frames.len(), 4);
+
+        // Each disk is 6 pixels tall, and 8 or 36 pixels wide, whichever
+        // tower it is on.
+        for (delay, pixels) in &decoded.frames {
+            assert_eq!(*delay, 25);
+            let count = |colour| pixels.iter().filter(|&&pixel| pixel == colour).count();
+            assert_eq!(count(disk_colour(0)), 8 * 6);
+            assert_eq!(count(disk_colour(1)), 36 * 6);
+        }
+        // The small disk starts on the left, and ends in the middle.
+        let column = |pixels: &[u8]| {
+            let index = pixels
+                .iter()
+                .position(|&pixel| pixel == disk_colour(0))
+                .unwrap();
+            index % 120 / 40
+        };
+        assert_eq!(column(&decoded.frames[0].1), 0);
+        assert_eq!(column(&decoded.frames[1].1), 2);
+        assert_eq!(column(&decoded.frames[3].1), 1);
+    }
+
+    #[test]
+    fn test_too_many_disks() {
+        let (width, height) = (480, 180);
+        assert_eq!(max_disks(height), 152);
+        // Every disk is one pixel tall, the last just below the top margin.
+        let pixels = rasterise(&TowerSet::with_pegs(3, 152), width, height);
+        let rows = pixels.chunks(width);
+        let drawn = rows.filter(|row| row.contains(&disk_colour(0))).count();
+        assert_eq!(drawn, 1);
+        assert!(pixels[10 * width..11 * width].contains(&disk_colour(0)));
+        // Any more, and the smallest are left out: only the 162 largest fit
+        // between the base and the top of the frame.
+        let pixels = rasterise(&TowerSet::with_pegs(3, 200), width, height);
+        assert!(!pixels.contains(&disk_colour(37)));
+        assert!(pixels.contains(&disk_colour(38)));
+    }
+
+    #[test]
+    fn test_coloured_disks() {
+        let (width, height) = (120, 60);
+        let mut towers = TowerSet::with_pegs(3, 0);
+        towers[A] = Tower::with_copies(2, 2);
+        // Disks are filled by colour, whatever their size.
+        let pixels = rasterise(&towers, width, height);
+        let rows: Vec<_> = pixels.chunks(width).collect();
+        let count = |colour| rows.iter().filter(|row| row.contains(&colour)).count();
+        assert_eq!(count(disk_colour(0)), count(disk_colour(1)));
+        assert!(count(disk_colour(0)) > 0);
+        assert!(!pixels.contains(&disk_colour(2)));
+        // The largest copies are scaled to the column, as a single disk is.
+        let widest = rows
+            .iter()
+            .map(|row| row.iter().filter(|&&p| p > PEGS).count());
+        assert_eq!(widest.max(), Some(40 * 9 / 10));
+    }
+
+    #[test]
+    fn test_palette() {
+        let palette = palette(300);
+        assert_eq!(palette.len(), 256);
+        assert_eq!(disk_colour(0), 2);
+        assert_eq!(disk_colour(253), 255);
+        assert_eq!(disk_colour(254), 2);
+        // Neighbouring disks differ in colour.
+        assert!(palette.windows(2).all(|pair| pair[0] != pair[1]));
+    }
+}
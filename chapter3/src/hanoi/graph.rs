@@ -0,0 +1,242 @@
+//! The graph of every legal state of the three-peg puzzle, with an edge for
+//! each legal move.  With n disks there are 3^n states, since each disk can
+//! be on any peg and there is only one legal order for the disks on a peg.
+//! The graph has the shape of a Sierpinski triangle: three copies of the graph
+//! for n - 1 disks, one for each position of the largest disk, joined at
+//! their corners by the three moves of the largest disk.  That is why the
+//! shortest path between two corners takes 2^n - 1 moves.
+
+use std::collections::VecDeque;
+use std::fmt::{self, Write};
+
+use super::packed::PackedState;
+
+/// The number of pegs in the graph.
+const PEGS: usize = 3;
+
+/// Every legal state and move of the puzzle.  States are numbered by reading
+/// the pegs of the disks, smallest first, as the digits of a base-3 number,
+/// least significant first, which is the rank of their packed form.  No
+/// state has more than three moves, so they are stored in a flat list, three
+/// to a state, with `usize::MAX` for any that are missing.
+pub struct StateGraph {
+    disks: usize,
+    neighbours: Vec<usize>,
+}
+
+impl StateGraph {
+    /// Builds the graph for the specified number of disks.  Panics if they
+    /// will not pack into a `PackedState`.
+    pub fn new(disks: usize) -> StateGraph {
+        assert!(disks <= PackedState::capacity(PEGS), "too many disks");
+        let mut graph = StateGraph {
+            disks,
+            neighbours: vec![usize::MAX; PEGS * PEGS.pow(disks as u32)],
+        };
+        for state in 0..graph.state_count() {
+            let successors = graph.packed(state).successors();
+            for (index, (_, next)) in successors.enumerate() {
+                graph.neighbours[state * PEGS + index] = next.rank() as usize;
+            }
+        }
+        graph
+    }
+
+    /// Returns the number of states, which is 3^n.
+    pub fn state_count(&self) -> usize {
+        self.neighbours.len() / PEGS
+    }
+
+    /// Returns the number of moves between states, counting a move and its
+    /// reverse once.
+    pub fn edge_count(&self) -> usize {
+        let ends = self.neighbours.iter().filter(|&&next| next != usize::MAX);
+        ends.count() / 2
+    }
+
+    /// Returns the states one legal move away from the specified state.
+    fn neighbours(&self, state: usize) -> impl Iterator<Item = usize> + '_ {
+        let moves = &self.neighbours[state * PEGS..(state + 1) * PEGS];
+        moves.iter().copied().filter(|&next| next != usize::MAX)
+    }
+
+    /// Returns the number of a state.  Panics unless it has three pegs and
+    /// as many disks as the graph.
+    pub fn state(&self, packed: PackedState) -> usize {
+        assert_eq!(packed.pegs(), PEGS);
+        assert_eq!(packed.disks(), self.disks);
+        packed.rank() as usize
+    }
+
+    /// Returns a state in packed form, given its number.
+    fn packed(&self, state: usize) -> PackedState {
+        PackedState::unrank(PEGS, self.disks, state as u128).expect("graphs are small")
+    }
+
+    /// Returns the number of moves from the specified state to every state,
+    /// indexed by state number, found by breadth-first search, together with
+    /// the state before each on a shortest path.
+    fn search(&self, from: usize) -> (Vec<u32>, Vec<usize>) {
+        let mut distances = vec![u32::MAX; self.state_count()];
+        let mut previous = vec![usize::MAX; self.state_count()];
+        let mut queue = VecDeque::from([from]);
+        distances[from] = 0;
+        while let Some(state) = queue.pop_front() {
+            for next in self.neighbours(state) {
+                if distances[next] == u32::MAX {
+                    distances[next] = distances[state] + 1;
+                    previous[next] = state;
+                    queue.push_back(next);
+                }
+            }
+        }
+        (distances, previous)
+    }
+
+    /// Returns the number of moves from the specified state to every state,
+    /// indexed by state number.
+    pub fn distances(&self, from: usize) -> Vec<u32> {
+        self.search(from).0
+    }
+
+    /// Returns the states along a shortest path between two states,
+    /// including both ends.
+    pub fn shortest_path(&self, from: usize, to: usize) -> Vec<usize> {
+        let (_, previous) = self.search(from);
+        let mut path = vec![to];
+        while let Some(&state) = path.last().filter(|&&state| state != from) {
+            path.push(previous[state]);
+        }
+        path.reverse();
+        path
+    }
+
+    /// Returns the greatest number of moves from the specified state to any
+    /// other.
+    pub fn eccentricity(&self, state: usize) -> u32 {
+        self.distances(state).into_iter().max().unwrap_or_default()
+    }
+
+    /// Returns the greatest number of moves between any two states, which
+    /// takes a search from every state.
+    pub fn diameter(&self) -> u32 {
+        (0..self.state_count())
+            .map(|state| self.eccentricity(state))
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Returns the label of a state: the peg of each disk, largest first.
+    fn label(&self, state: usize) -> String {
+        let positions = self.packed(state).positions();
+        positions
+            .iter()
+            .rev()
+            .map(|selector| selector.label())
+            .collect()
+    }
+
+    /// Writes the graph in the DOT language of Graphviz.  The `neato` layout
+    /// shows the Sierpinski triangle best.
+    pub fn write_dot(&self, out: &mut impl Write) -> fmt::Result {
+        writeln!(out, "graph hanoi {{")?;
+        writeln!(out, "    layout=neato;")?;
+        writeln!(out, "    node [shape=circle, fontsize=8, margin=0];")?;
+        for state in 0..self.state_count() {
+            writeln!(out, "    s{state} [label=\"{}\"];", self.label(state))?;
+        }
+        for state in 0..self.state_count() {
+            for next in self.neighbours(state).filter(|&next| state < next) {
+                writeln!(out, "    s{state} -- s{next};")?;
+            }
+        }
+        writeln!(out, "}}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hanoi::{solve, TowerSelector, TowerSelectorSet, TowerSet};
+
+    const A: TowerSelector = TowerSelector::A;
+    const B: TowerSelector = TowerSelector::B;
+    const C: TowerSelector = TowerSelector::C;
+
+    #[test]
+    fn test_counts() {
+        for disks in 0..=6 {
+            let graph = StateGraph::new(disks);
+            let states = 3usize.pow(disks as u32);
+            assert_eq!(graph.state_count(), states);
+            // Every state but the three corners has three moves; the corners
+            // have two.
+            assert_eq!(graph.edge_count(), 3 * (states - 1) / 2);
+        }
+    }
+
+    #[test]
+    fn test_state_numbers() {
+        let graph = StateGraph::new(3);
+        for state in 0..graph.state_count() {
+            assert_eq!(graph.state(graph.packed(state)), state);
+        }
+        let towers = TowerSet::from_positions(3, &[B, C, A]);
+        let state = graph.state(PackedState::try_from(&towers).unwrap());
+        assert_eq!(state, 1 + 2 * 3);
+        assert_eq!(graph.label(state), "ACB");
+    }
+
+    #[test]
+    fn test_distance_matches_solve() {
+        for disks in 1..=7 {
+            let graph = StateGraph::new(disks);
+            let from = graph.state(PackedState::new(3, disks, A).unwrap());
+            let to = graph.state(PackedState::new(3, disks, B).unwrap());
+            let mut count = 0;
+            let towers = TowerSet::with_pegs(3, disks);
+            solve(towers, disks, TowerSelectorSet::new(), &mut |_, _| {
+                count += 1
+            })
+            .unwrap();
+            assert_eq!(graph.distances(from)[to], count);
+
+            // Every step along the shortest path is a legal move.
+            let path = graph.shortest_path(from, to);
+            assert_eq!(path.len() as u32, count + 1);
+            for pair in path.windows(2) {
+                assert!(graph.neighbours(pair[0]).any(|next| next == pair[1]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_diameter() {
+        for disks in 1..=5 {
+            let graph = StateGraph::new(disks);
+            let corner = graph.state(PackedState::new(3, disks, A).unwrap());
+            let most = (1 << disks) - 1;
+            assert_eq!(graph.eccentricity(corner), most);
+            assert_eq!(graph.diameter(), most);
+        }
+    }
+
+    #[test]
+    fn test_dot() {
+        let mut dot = String::new();
+        StateGraph::new(1).write_dot(&mut dot).unwrap();
+        let want = [
+            "graph hanoi {",
+            "    layout=neato;",
+            "    node [shape=circle, fontsize=8, margin=0];",
+            "    s0 [label=\"A\"];",
+            "    s1 [label=\"B\"];",
+            "    s2 [label=\"C\"];",
+            "    s0 -- s1;",
+            "    s0 -- s2;",
+            "    s1 -- s2;",
+            "}",
+        ];
+        assert_eq!(dot, want.join("\n") + "\n");
+    }
+}
@@ -0,0 +1,1960 @@
+//! The Tower of Hanoi puzzle from Chapter 3 of The Recursive Book of
+//! Recursion: disks, towers, and the rules for moving disks between them;
+//! solvers, from the book's recursive one to searches for variants of the
+//! puzzle; and ways of drawing, recording, and replaying games.  The
+//! `tower-of-hanoi-solver` program is a command-line front end to it.
+//!
+//! ```
+//! use part3::hanoi::{moves, play, TowerSelectorSet, TowerSet};
+//!
+//! let towers = TowerSet::with_pegs(3, 3);
+//! let selectors = TowerSelectorSet::new();
+//! let solved = play(towers, moves(3, selectors), &mut |_, _| {}).unwrap();
+//! assert_eq!(solved, TowerSet::from_positions(3, &[selectors.target; 3]));
+//! ```
+//!
+//! While this Tower of Hanoi solver here is modeled on the one from Chapter 3
+//! of The Recursive Book of Recursion, it does not mimic the structure of the
+//! book's sample code as closely as earlier exercises in this repository.
+//! While a shorter version of this solver is certainly possible--for example,
+//! by using numbers and stacks to represent disks and towers directly, rather
+//! than wrapping them in bespoke Disk and Tower types--such an implementation
+//! would not be idiomatic Rust.
+//!
+//! Tiny programs like those featured earlier in the book are so simple that
+//! good structure and conventions are not critical to their clarity or
+//! maintainability.  The Tower of Hanoi exercise, however, seems complex
+//! enough to merit a slightly more disciplined approach, which anyway is a
+//! better fit for Rust.  Even in Python and JavaScript, the reader may enjoy
+//! replacing the book's use of global variables with function arguments and
+//! returns values, and encapsulating the book's raw numbers, strings, and
+//! arrays with object-oriented classes akin to the Rust types defined here.
+
+pub mod bot;
+pub mod cast;
+pub mod gif;
+pub mod graph;
+pub mod notation;
+pub mod packed;
+pub mod random;
+pub mod render;
+pub mod rules;
+pub mod save;
+pub mod svg;
+pub mod trace;
+pub mod variant;
+
+use rules::Rules;
+use save::SavedGame;
+use std::error::Error;
+use std::ops::{Index, IndexMut};
+use std::path::Path;
+use std::str::FromStr;
+use std::{fmt, io};
+
+/// A disk, which may rest only on a disk of the same size or larger.  The
+/// colour tells apart disks of the same size, and in the magnetic puzzle,
+/// which face is up.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Disk {
+    /// The size of the disk, from 1 for the smallest.
+    pub radius: usize,
+    /// The colour of the disk, from 0 for the first.
+    pub colour: usize,
+}
+
+impl Disk {
+    /// Returns which fill a drawing gives the disk, counting from zero: its
+    /// colour if the drawing shows colours, or else its size.
+    pub fn shade(&self, by_colour: bool) -> usize {
+        if by_colour {
+            self.colour
+        } else {
+            self.radius - 1
+        }
+    }
+}
+
+impl fmt::Display for Disk {
+    /// Writes the radius, followed by the colour as a letter of the alphabet,
+    /// unless it is the first colour, `a`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.radius)?;
+        if self.colour > 0 {
+            write!(f, "{}", char::from(b'a' + (self.colour % 26) as u8))?;
+        }
+        Ok(())
+    }
+}
+
+/// A stack of disks on a peg.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Tower {
+    /// The disks on the tower.
+    pub disks: Vec<Disk>, // a stack, ordered from bottom to top
+}
+
+impl Tower {
+    /// Returns the disk on top of the tower, if any.
+    pub fn top(&self) -> Option<&Disk> {
+        self.disks.last()
+    }
+
+    /// Removes the disk on top of the tower, if any, and returns it.
+    pub fn pop(&mut self) -> Option<Disk> {
+        self.disks.pop()
+    }
+
+    /// Puts a disk on top of the tower, whatever its size.
+    pub fn push(&mut self, disk: Disk) {
+        self.disks.push(disk);
+    }
+}
+
+impl Tower {
+    /// Returns a tower of n disks, from radius n at the bottom to 1 at the top.
+    pub fn with_disks(n: usize) -> Tower {
+        Tower::with_copies(n, 1)
+    }
+
+    /// Returns a tower with the specified number of disks of each size,
+    /// coloured in order from the bottom of each group.
+    pub fn with_copies(n: usize, copies: usize) -> Tower {
+        let disks = (1..=n)
+            .rev()
+            .flat_map(|radius| (0..copies).map(move |colour| Disk { radius, colour }));
+        Tower {
+            disks: disks.collect(),
+        }
+    }
+}
+
+/// The error returned when a string is not the label of a tower.
+#[derive(Debug)]
+pub struct ParseTowerSelectorError(String);
+
+impl fmt::Display for ParseTowerSelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: bad tower selector", self.0)
+    }
+}
+
+impl Error for ParseTowerSelectorError {}
+
+/// The error returned when a saved game or a list of moves cannot be parsed,
+/// giving where the problem was found.
+#[derive(Debug, Eq, PartialEq)]
+pub struct ParseTextError {
+    line: usize,   // one-based
+    column: usize, // one-based, in characters
+    message: String,
+}
+
+impl ParseTextError {
+    /// Returns an error for the character at the specified byte offset in a
+    /// line, numbering lines and columns from zero.
+    fn at(line: usize, text: &str, offset: usize, message: impl Into<String>) -> ParseTextError {
+        ParseTextError {
+            line: line + 1,
+            column: text[..offset].chars().count() + 1,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseTextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for ParseTextError {}
+
+/// Index of a tower within a TowerSet, labeled by a letter of the alphabet.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TowerSelector(pub usize);
+
+impl TowerSelector {
+    /// The first tower.
+    pub const A: TowerSelector = TowerSelector(0);
+    /// The second tower.
+    pub const B: TowerSelector = TowerSelector(1);
+    /// The third tower.
+    pub const C: TowerSelector = TowerSelector(2);
+
+    /// The number of towers that can be labeled with a single letter.
+    pub const MAX: usize = 26;
+
+    /// Returns the letter labelling the tower.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tower is not among the first `MAX`, which have no letter.
+    pub fn label(self) -> char {
+        assert!(self.0 < Self::MAX, "tower {} has no letter", self.0);
+        char::from(b'A' + self.0 as u8)
+    }
+}
+
+impl fmt::Display for TowerSelector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+impl FromStr for TowerSelector {
+    type Err = ParseTowerSelectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.as_bytes() {
+            [letter @ b'A'..=b'Z'] => Ok(TowerSelector(usize::from(letter - b'A'))),
+            _ => Err(ParseTowerSelectorError(s.to_string())),
+        }
+    }
+}
+
+/// The three towers that play a part in moving a stack of disks: the one they
+/// start on, the one they end on, and the one in between.
+#[derive(Clone, Copy)]
+pub struct TowerSelectorSet {
+    /// The tower the disks start on, which the book calls `startTower`.
+    pub source: TowerSelector,
+    /// The tower the disks end on, which the book calls `endTower`.
+    pub target: TowerSelector,
+    /// The tower in between, which the book calls `tempTower`.
+    pub buffer: TowerSelector,
+}
+
+impl TowerSelectorSet {
+    /// Returns a set of three towers.  Panics unless they differ.
+    pub fn from_parts(
+        source: TowerSelector,
+        target: TowerSelector,
+        buffer: TowerSelector,
+    ) -> TowerSelectorSet {
+        (source == target || source == buffer || target == buffer)
+            .then(|| panic!("tower selectors should be unique: {source}, {target}, {buffer}"));
+        TowerSelectorSet {
+            source,
+            target,
+            buffer,
+        }
+    }
+
+    /// Returns towers A, B, and C as the source, target, and buffer.
+    pub fn new() -> TowerSelectorSet {
+        TowerSelectorSet {
+            source: TowerSelector::A,
+            target: TowerSelector::B,
+            buffer: TowerSelector::C,
+        }
+    }
+}
+
+impl Default for TowerSelectorSet {
+    fn default() -> Self {
+        TowerSelectorSet::new()
+    }
+}
+
+/// Reasons a disk cannot be moved from one tower to another.
+#[derive(Debug, Eq, PartialEq)]
+pub enum IllegalMoveError {
+    /// The source tower has no disk to move.
+    EmptyTower(TowerSelector),
+    /// The disk on top of the source tower is larger than the disk on top of
+    /// the target tower.
+    OversizeDisk {
+        /// The tower the disk would leave.
+        source: TowerSelector,
+        /// The tower the disk would land on.
+        target: TowerSelector,
+        /// The radius of the disk.
+        radius: usize,
+        /// The radius of the disk on top of the target tower.
+        below: usize,
+    },
+    /// The source and target towers are the same tower.
+    SameTower(TowerSelector),
+    /// There is no tower with this label in the set.
+    NoSuchTower(TowerSelector),
+    /// The facing sides of two magnetic disks would repel each other.
+    RepellingDisk {
+        /// The tower the disk would leave.
+        source: TowerSelector,
+        /// The tower the disk would land on.
+        target: TowerSelector,
+        /// The radius of the disk.
+        radius: usize,
+        /// The radius of the disk on top of the target tower.
+        below: usize,
+    },
+    /// The variant of the puzzle does not allow moves between these towers.
+    ForbiddenMove {
+        /// The tower the disk would leave.
+        source: TowerSelector,
+        /// The tower the disk would land on.
+        target: TowerSelector,
+    },
+}
+
+impl fmt::Display for IllegalMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IllegalMoveError::EmptyTower(source) => write!(f, "tower {source} is empty"),
+            IllegalMoveError::OversizeDisk {
+                source,
+                target,
+                radius,
+                below,
+            } => write!(
+                f,
+                "disk {radius} from tower {source} cannot go on smaller disk {below} on tower {target}"
+            ),
+            IllegalMoveError::SameTower(tower) => {
+                write!(f, "tower {tower} cannot be both source and target")
+            }
+            IllegalMoveError::NoSuchTower(tower) => write!(f, "there is no tower {tower}"),
+            IllegalMoveError::RepellingDisk {
+                source,
+                target,
+                radius,
+                below,
+            } => write!(
+                f,
+                "disk {radius} from tower {source} would be repelled by disk {below} on tower {target}"
+            ),
+            IllegalMoveError::ForbiddenMove { source, target } => {
+                write!(f, "the rules forbid moving from tower {source} to tower {target}")
+            }
+        }
+    }
+}
+
+impl Error for IllegalMoveError {}
+
+/// The towers of the puzzle, labelled from A.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TowerSet {
+    /// The towers, in the order of their labels.
+    pub towers: Vec<Tower>,
+}
+
+impl TowerSet {
+    /// Returns the specified number of towers, the first of which holds n
+    /// disks.
+    pub fn with_pegs(pegs: usize, n: usize) -> TowerSet {
+        assert!(
+            (1..=TowerSelector::MAX).contains(&pegs),
+            "peg count should be from 1 to {}: {pegs}",
+            TowerSelector::MAX
+        );
+        let mut towers: Vec<Tower> = (0..pegs).map(|_| Tower::default()).collect();
+        towers[0] = Tower::with_disks(n);
+        TowerSet { towers }
+    }
+
+    /// Returns the selector of each tower, in order.
+    pub fn selectors(&self) -> impl Iterator<Item = TowerSelector> {
+        (0..self.towers.len()).map(TowerSelector)
+    }
+
+    /// Checks whether the top disk of the source tower may be moved onto the
+    /// target tower, without actually moving it.
+    pub fn check_move(
+        &self,
+        source: TowerSelector,
+        target: TowerSelector,
+    ) -> Result<(), IllegalMoveError> {
+        self.check_move_with(&Rules::Classic, source, target)
+    }
+
+    /// Checks whether the top disk of the source tower may be moved onto the
+    /// target tower under the rules of a variant of the puzzle.
+    pub fn check_move_with(
+        &self,
+        rules: &Rules,
+        source: TowerSelector,
+        target: TowerSelector,
+    ) -> Result<(), IllegalMoveError> {
+        if let Some(tower) = [source, target]
+            .into_iter()
+            .find(|tower| tower.0 >= self.towers.len())
+        {
+            return Err(IllegalMoveError::NoSuchTower(tower));
+        }
+        if source == target {
+            return Err(IllegalMoveError::SameTower(source));
+        }
+        if !rules.allows(source, target) {
+            return Err(IllegalMoveError::ForbiddenMove { source, target });
+        }
+        let disk = self[source]
+            .top()
+            .ok_or(IllegalMoveError::EmptyTower(source))?;
+        match self[target].top() {
+            Some(below) if below.radius < disk.radius => Err(IllegalMoveError::OversizeDisk {
+                source,
+                target,
+                radius: disk.radius,
+                below: below.radius,
+            }),
+            Some(below) if !rules.may_rest_on(&rules.landed(disk), below) => {
+                Err(IllegalMoveError::RepellingDisk {
+                    source,
+                    target,
+                    radius: disk.radius,
+                    below: below.radius,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Moves the top disk of the source tower onto the target tower, unless
+    /// doing so would break the rules of the puzzle.
+    ///
+    /// ```
+    /// use part3::hanoi::{IllegalMoveError, TowerSelector, TowerSet};
+    ///
+    /// let (a, b) = (TowerSelector::A, TowerSelector::B);
+    /// let mut towers = TowerSet::with_pegs(3, 2);
+    /// towers.move_disk(a, b).unwrap();
+    /// let error = towers.move_disk(a, b).unwrap_err();
+    /// assert!(matches!(error, IllegalMoveError::OversizeDisk { radius: 2, .. }));
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "disk 2 from tower A cannot go on smaller disk 1 on tower B"
+    /// );
+    /// ```
+    pub fn move_disk(
+        &mut self,
+        source: TowerSelector,
+        target: TowerSelector,
+    ) -> Result<(), IllegalMoveError> {
+        self.move_disk_with(&Rules::Classic, source, target)
+    }
+
+    /// Moves the top disk of the source tower onto the target tower, unless
+    /// doing so would break the rules of a variant of the puzzle.
+    pub fn move_disk_with(
+        &mut self,
+        rules: &Rules,
+        source: TowerSelector,
+        target: TowerSelector,
+    ) -> Result<(), IllegalMoveError> {
+        self.check_move_with(rules, source, target)?;
+        let disk = self[source]
+            .pop()
+            .expect("checked tower should not be empty");
+        self[target].push(rules.landed(&disk));
+        Ok(())
+    }
+}
+
+impl<'a> IntoIterator for &'a TowerSet {
+    type Item = &'a Tower;
+    type IntoIter = std::slice::Iter<'a, Tower>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.towers.iter()
+    }
+}
+
+impl TowerSet {
+    /// Returns every disk, tower by tower, each from the bottom up.
+    pub fn disks(&self) -> impl Iterator<Item = &Disk> {
+        self.into_iter().flat_map(|tower| tower.disks.iter())
+    }
+
+    /// Returns whether any disk has a colour other than the first, in which
+    /// case drawings fill the disks by colour rather than by size.
+    pub fn is_coloured(&self) -> bool {
+        self.disks().any(|disk| disk.colour > 0)
+    }
+
+    /// Returns the tower holding each disk, indexed by radius minus one.
+    /// Panics unless the disks have radii 1 through n.
+    pub fn positions(&self) -> Vec<TowerSelector> {
+        let mut positions = vec![None; self.disks().count()];
+        for (selector, tower) in self.selectors().zip(self) {
+            for disk in &tower.disks {
+                let position = positions
+                    .get_mut(disk.radius.wrapping_sub(1))
+                    .unwrap_or_else(|| panic!("disk {} should be numbered", disk.radius));
+                assert!(position.is_none(), "disk {} is duplicated", disk.radius);
+                *position = Some(selector);
+            }
+        }
+        positions.into_iter().flatten().collect()
+    }
+
+    /// Returns the specified number of towers, with each disk on the tower
+    /// given by its position, indexed by radius minus one.  Disks are stacked
+    /// largest first, so the result is always legal.
+    pub fn from_positions(pegs: usize, positions: &[TowerSelector]) -> TowerSet {
+        let mut towers = TowerSet::with_pegs(pegs, 0);
+        for (index, &selector) in positions.iter().enumerate().rev() {
+            towers[selector].push(Disk {
+                radius: index + 1,
+                colour: 0,
+            });
+        }
+        towers
+    }
+}
+
+/// Helper for implementing the Display trait.  Each disk is drawn as `@`s
+/// either side of a two-column label, the same width as the poles, so the
+/// towers line up whatever the radii.  Disks of other colours are drawn with
+/// other characters in place of the `@`s.
+pub struct TowerSetDisplay<'a> {
+    towers: &'a TowerSet,
+    radius: usize,     // of the largest disk
+    half_width: usize, // the number of `@`s either side of the largest disk
+}
+
+impl TowerSetDisplay<'_> {
+    /// Returns a drawing of the towers, wide enough for the largest disk.
+    pub fn new(towers: &TowerSet) -> TowerSetDisplay<'_> {
+        let radius = towers
+            .disks()
+            .map(|disk| disk.radius)
+            .max()
+            .unwrap_or_default();
+        TowerSetDisplay {
+            towers,
+            radius,
+            half_width: radius,
+        }
+    }
+
+    /// Narrows the disks, if necessary, so that each line fits in the
+    /// specified number of columns.  Every disk keeps at least one `@` on
+    /// each side while there is room for it, so larger disks never look
+    /// smaller than the disks above them.
+    pub fn fit_width(mut self, columns: usize) -> Self {
+        let available = (columns / self.towers.towers.len()).saturating_sub(3) / 2;
+        self.half_width = self.half_width.min(available);
+        self
+    }
+
+    /// Returns the number of `@`s either side of a disk's label.
+    fn fill(&self, radius: usize) -> usize {
+        (radius * self.half_width).div_ceil(self.radius)
+    }
+
+    /// Returns a disk's two-column label: an underscore and the radius below
+    /// 10, the bare radius below 100, and otherwise just more `@`s.
+    fn label(radius: usize) -> String {
+        match radius {
+            0..=9 => format!("_{radius}"),
+            10..=99 => radius.to_string(),
+            _ => "@@".to_string(),
+        }
+    }
+
+    /// Returns the character that fills a disk of the specified colour.
+    fn fill_char(colour: usize) -> char {
+        const FILLS: [char; 8] = ['@', '#', '%', '&', '+', '=', '*', '~'];
+        FILLS[colour % FILLS.len()]
+    }
+
+    fn write_pole(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, " {:^2$}||{:^2$}", "", "", self.half_width)
+    }
+
+    fn print_pole_tops(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for _ in self.towers {
+            self.write_pole(f)?;
+        }
+        writeln!(f)
+    }
+
+    fn print_disks(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let disk_count = self.towers.disks().count();
+        for depth in 0..disk_count {
+            for tower in self.towers {
+                if let Some(disk) = tower.disks.get(disk_count - depth - 1) {
+                    // There's a disk at this depth on this tower.  Print it,
+                    // surrounded by space.
+                    let fill = self.fill(disk.radius);
+                    write!(
+                        f,
+                        " {blank:gap$}{fill}{label}{fill}{blank:gap$}",
+                        blank = "",
+                        fill = TowerSetDisplay::fill_char(disk.colour)
+                            .to_string()
+                            .repeat(fill),
+                        label = TowerSetDisplay::label(disk.radius),
+                        gap = self.half_width - fill,
+                    )?;
+                } else {
+                    // There's no disk at this depth on this tower.  Print the
+                    // pole, surrounded by space.
+                    self.write_pole(f)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+
+    fn print_labels(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for label in self.towers.selectors() {
+            write!(f, " {:^2$} {label}{:^2$}", "", "", self.half_width)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TowerSetDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.print_pole_tops(f)?;
+        self.print_disks(f)?;
+        self.print_labels(f)
+    }
+}
+
+impl fmt::Display for TowerSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        TowerSetDisplay::new(self).fmt(f)
+    }
+}
+
+impl Index<TowerSelector> for TowerSet {
+    type Output = Tower;
+
+    fn index(&self, index: TowerSelector) -> &Self::Output {
+        &self.towers[index.0]
+    }
+}
+
+impl IndexMut<TowerSelector> for TowerSet {
+    fn index_mut(&mut self, index: TowerSelector) -> &mut Self::Output {
+        &mut self.towers[index.0]
+    }
+}
+
+/// Work remaining for the `Steps` iterator: either a recursive call not yet
+/// begun, or a move waiting for the calls before it to finish, each with the
+/// depth of the call it belongs to.
+enum Pending {
+    Call(usize, TowerSelectorSet, usize),
+    Move(Move, usize),
+}
+
+/// A step of the recursive solution: either the start of a call, or a move.
+#[derive(Clone, Copy)]
+pub enum Step {
+    /// A call to move a tower of this height between these towers.
+    Call {
+        /// The number of disks to move, which the book calls `numberOfDisks`.
+        height: usize,
+        /// The towers the call moves the disks between.
+        selectors: TowerSelectorSet,
+        /// The number of calls above this one on the stack.
+        depth: usize,
+    },
+    /// A move made by the call at this depth, between its two recursive
+    /// calls.
+    Move {
+        /// The move itself.
+        made: Move,
+        /// The number of calls above the one making the move on the stack.
+        depth: usize,
+    },
+}
+
+/// Iterator over the steps of the recursive solution.  Rather than making the
+/// recursive calls up front, it keeps a stack of the work remaining in each
+/// call along the path to the current step, so it never holds more than about
+/// two entries per disk.
+struct Steps {
+    stack: Vec<Pending>,
+}
+
+impl Iterator for Steps {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Step> {
+        match self.stack.pop()? {
+            Pending::Move(made, depth) => Some(Step::Move { made, depth }),
+            Pending::Call(height, selectors, depth) => {
+                if height > 0 {
+                    // RECURSIVE CASE: Push the work in reverse order, so the
+                    // first recursive call is popped first.
+                    let TowerSelectorSet {
+                        source,
+                        target,
+                        buffer,
+                    } = selectors;
+                    self.stack.push(Pending::Call(
+                        height - 1,
+                        TowerSelectorSet::from_parts(buffer, target, source),
+                        depth + 1,
+                    ));
+                    self.stack.push(Pending::Move((source, target), depth));
+                    self.stack.push(Pending::Call(
+                        height - 1,
+                        TowerSelectorSet::from_parts(source, buffer, target),
+                        depth + 1,
+                    ));
+                }
+                // BASE CASE: No disks to move, and nothing more to do.
+                Some(Step::Call {
+                    height,
+                    selectors,
+                    depth,
+                })
+            }
+        }
+    }
+}
+
+/// Returns the calls and moves of the recursive solution, lazily, in the order
+/// it makes them.  `moves` keeps just the moves, and `trace::Trace` records
+/// the calls.
+pub fn steps(height: usize, selectors: TowerSelectorSet) -> impl Iterator<Item = Step> {
+    Steps {
+        stack: vec![Pending::Call(height, selectors, 0)],
+    }
+}
+
+/// Returns the moves of the recursive solution, lazily.  Whereas the book
+/// keeps the towers in a global variable and moves disks directly, we yield
+/// moves for the caller to apply.  We also group the tower selectors into a
+/// single struct, rather than passing them all as separate function arguments
+/// as the book does.  What the book calls `numberOfDisks`, we call `height`.
+/// These are purely stylistic choices.
+///
+/// ```
+/// use part3::hanoi::{moves, TowerSelector, TowerSelectorSet};
+///
+/// let (a, b, c) = (TowerSelector::A, TowerSelector::B, TowerSelector::C);
+/// let solution: Vec<_> = moves(2, TowerSelectorSet::new()).collect();
+/// assert_eq!(solution, [(a, c), (a, b), (c, b)]);
+/// ```
+pub fn moves(height: usize, selectors: TowerSelectorSet) -> impl Iterator<Item = Move> {
+    steps(height, selectors).filter_map(|step| match step {
+        Step::Move { made, .. } => Some(made),
+        Step::Call { .. } => None,
+    })
+}
+
+/// Applies the moves to the towers, calling `on_move` with each move and the
+/// towers after it.
+pub fn play(
+    towers: TowerSet,
+    moves: impl IntoIterator<Item = Move>,
+    on_move: &mut impl FnMut(Move, &TowerSet),
+) -> Result<TowerSet, IllegalMoveError> {
+    play_with(&Rules::Classic, towers, moves, on_move)
+}
+
+/// Applies the moves to the towers under the rules of a variant of the
+/// puzzle, calling `on_move` with each move and the towers after it.
+pub fn play_with(
+    rules: &Rules,
+    mut towers: TowerSet,
+    moves: impl IntoIterator<Item = Move>,
+    on_move: &mut impl FnMut(Move, &TowerSet),
+) -> Result<TowerSet, IllegalMoveError> {
+    for (source, target) in moves {
+        towers.move_disk_with(rules, source, target)?;
+        on_move((source, target), &towers);
+    }
+    Ok(towers)
+}
+
+/// Applies the moves of the recursive solution to the towers, calling
+/// `on_move` with each move and the towers after it.
+pub fn solve(
+    towers: TowerSet,
+    height: usize,
+    selectors: TowerSelectorSet,
+    on_move: &mut impl FnMut(Move, &TowerSet),
+) -> Result<TowerSet, IllegalMoveError> {
+    play(towers, moves(height, selectors), on_move)
+}
+
+/// Returns the moves of the classic iterative solution, which alternates
+/// between moving the smallest disk one tower along a fixed cycle, and making
+/// the only legal move that does not involve the smallest disk.  The smallest
+/// disk cycles toward the target first if the height is odd, or toward the
+/// buffer first if it is even, so that the tower ends up on the target.
+pub fn moves_iterative(height: usize, selectors: TowerSelectorSet) -> Vec<Move> {
+    let TowerSelectorSet {
+        source,
+        target,
+        buffer,
+    } = selectors;
+    let cycle = if height % 2 == 1 {
+        [source, target, buffer]
+    } else {
+        [source, buffer, target]
+    };
+    let mut towers = TowerSet::from_positions(3, &vec![source; height]);
+    let mut moves = Vec::new();
+    let mut smallest = 0; // The index in `cycle` of the smallest disk.
+    let total = towers.disks().count();
+    while towers[target].disks.len() < total {
+        if moves.len() % 2 == 0 {
+            let next = (smallest + 1) % 3;
+            moves.push((cycle[smallest], cycle[next]));
+            smallest = next;
+        } else {
+            let (a, b) = (cycle[(smallest + 1) % 3], cycle[(smallest + 2) % 3]);
+            moves.push(if towers.check_move(a, b).is_ok() {
+                (a, b)
+            } else {
+                (b, a)
+            });
+        }
+        let &(from, to) = moves.last().expect("a move was just pushed");
+        towers
+            .move_disk(from, to)
+            .expect("the iterative solution should make only legal moves");
+    }
+    moves
+}
+
+/// Returns the moves of the solution computed bit by bit from each move
+/// number m, which moves a disk from tower `(m & m - 1) % 3` to tower
+/// `((m | m - 1) + 1) % 3`.  Towers are numbered so that the tower ends up on
+/// tower 2 if the height is odd, or on tower 1 if it is even.
+///
+/// The arithmetic is done in a u128, as the last move of 64 disks has
+/// `m | m - 1` equal to `u64::MAX`, one less than 2^64.
+pub fn moves_bitwise(height: usize, selectors: TowerSelectorSet) -> impl Iterator<Item = Move> {
+    assert!(height <= 64, "bitwise solution supports at most 64 disks");
+    let TowerSelectorSet {
+        source,
+        target,
+        buffer,
+    } = selectors;
+    let towers = if height % 2 == 1 {
+        [source, buffer, target]
+    } else {
+        [source, target, buffer]
+    };
+    let total = u64::MAX.checked_shr(64 - height as u32).unwrap_or(0);
+    (1..=total).map(move |m| {
+        let (from, to) = bitwise_move(m);
+        (towers[from], towers[to])
+    })
+}
+
+/// Returns the numbers of the towers between which `moves_bitwise` makes move
+/// number m, counting from one.
+fn bitwise_move(m: u64) -> (usize, usize) {
+    let m = u128::from(m);
+    let from = (m & (m - 1)) % 3;
+    let to = ((m | (m - 1)) + 1) % 3;
+    (from as usize, to as usize)
+}
+
+/// Returns the moves of the recursive solution, computed by emulating the
+/// call stack explicitly, as in `factorial-emulate-recursion.rs` from Chapter
+/// 2.  Each "call" has two recursive calls, so it may "return" to either of
+/// two addresses.
+pub fn moves_emulate_recursion(height: usize, selectors: TowerSelectorSet) -> Vec<Move> {
+    #[derive(Clone, Copy)]
+    enum ReturnAddr {
+        Start,
+        AfterFirstRecursiveCall,
+        AfterSecondRecursiveCall,
+    }
+
+    use ReturnAddr::*;
+
+    #[derive(Clone, Copy)]
+    struct Call {
+        return_addr: ReturnAddr,
+        height: usize,
+        selectors: TowerSelectorSet,
+    }
+
+    let mut moves = Vec::new();
+    let mut call_stack = vec![];
+
+    // "Call" the "solve() function".
+    call_stack.push(Call {
+        return_addr: Start,
+        height,
+        selectors,
+    });
+
+    while let Some(&Call {
+        return_addr,
+        height,
+        selectors,
+    }) = call_stack.last()
+    {
+        // The body of the "solve() function":
+
+        let TowerSelectorSet {
+            source,
+            target,
+            buffer,
+        } = selectors;
+        let len = call_stack.len();
+        match return_addr {
+            Start => {
+                if height == 0 {
+                    // BASE CASE
+                    call_stack.pop(); // "Return" from "function call".
+                } else {
+                    // RECURSIVE CASE
+                    call_stack[len - 1].return_addr = AfterFirstRecursiveCall;
+
+                    // "Call" the "solve() function":
+                    call_stack.push(Call {
+                        return_addr: Start,
+                        height: height - 1,
+                        selectors: TowerSelectorSet::from_parts(source, buffer, target),
+                    });
+                }
+            }
+            AfterFirstRecursiveCall => {
+                moves.push((source, target));
+                call_stack[len - 1].return_addr = AfterSecondRecursiveCall;
+
+                // "Call" the "solve() function":
+                call_stack.push(Call {
+                    return_addr: Start,
+                    height: height - 1,
+                    selectors: TowerSelectorSet::from_parts(buffer, target, source),
+                });
+            }
+            AfterSecondRecursiveCall => {
+                call_stack.pop();
+            }
+        }
+    }
+
+    moves
+}
+
+/// The ways of computing the three-tower solution, all of which make the same
+/// moves.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Solver {
+    /// The recursive solution from the book.
+    Recursive,
+    /// The classic iterative solution, which moves the smallest disk every
+    /// other move.
+    Iterative,
+    /// A solution that finds each move from the bits of its number.
+    Bitwise,
+    /// The recursive solution, with an explicit stack in place of the call
+    /// stack.
+    EmulateRecursion,
+}
+
+impl FromStr for Solver {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "recursive" => Ok(Solver::Recursive),
+            "iterative" => Ok(Solver::Iterative),
+            "bitwise" => Ok(Solver::Bitwise),
+            "emulate-recursion" => Ok(Solver::EmulateRecursion),
+            _ => Err(format!("{s}: bad solver")),
+        }
+    }
+}
+
+/// Returns the number of moves the recursive solution makes before moving the
+/// largest of `height` disks, which is also the number it makes after.  Returns
+/// None if the count does not fit in a u64.
+pub fn half_moves(height: usize) -> Option<u64> {
+    match height {
+        0 => Some(0), // There is no largest disk, and no moves.
+        _ => 1u64
+            .checked_shl(u32::try_from(height - 1).ok()?)
+            .map(|half| half - 1),
+    }
+}
+
+/// Returns the k-th move (counting from one) of the recursive solution,
+/// without making the moves before it.  The solution moves the largest disk
+/// exactly halfway through, so each disk narrows the search to one half or the
+/// other, as in a binary search.
+pub fn nth_move(height: usize, k: u64, selectors: TowerSelectorSet) -> Option<Move> {
+    let TowerSelectorSet {
+        mut source,
+        mut target,
+        mut buffer,
+    } = selectors;
+    let mut k = k.checked_sub(1)?; // Count from zero.
+    for height in (1..=height).rev() {
+        match half_moves(height) {
+            Some(half) if k == half => return Some((source, target)),
+            Some(half) if k > half => {
+                // The move comes after the largest disk has moved, while the
+                // smaller disks move from the buffer to the target.
+                k -= half + 1;
+                (source, buffer) = (buffer, source);
+            }
+            _ => {
+                // The move comes before the largest disk has moved, while the
+                // smaller disks move from the source to the buffer.
+                (target, buffer) = (buffer, target);
+            }
+        }
+    }
+    None // There are fewer than k moves.
+}
+
+/// Returns the towers as they stand after the first k moves of the recursive
+/// solution, without making those moves.  Each disk is on the source tower if
+/// it has not moved yet, or on the target tower if it has, where the source and
+/// target of each disk depend on those of the larger disks.
+pub fn state_after(height: usize, k: u64, selectors: TowerSelectorSet) -> Option<TowerSet> {
+    let TowerSelectorSet {
+        mut source,
+        mut target,
+        mut buffer,
+    } = selectors;
+    let mut k = k;
+    let mut positions = vec![source; height];
+    for height in (1..=height).rev() {
+        match half_moves(height) {
+            Some(half) if k > half => {
+                positions[height - 1] = target;
+                k -= half + 1;
+                (source, buffer) = (buffer, source);
+            }
+            _ => {
+                positions[height - 1] = source;
+                (target, buffer) = (buffer, target);
+            }
+        }
+    }
+    // Any remaining move would be past the end of the solution.
+    (k == 0).then(|| TowerSet::from_positions(3, &positions))
+}
+
+/// Returns the number of moves the recursive solution has made to reach the
+/// specified towers, or None if they are not on its path.  This is the inverse
+/// of `state_after`.
+pub fn moves_made(towers: &TowerSet, selectors: TowerSelectorSet) -> Option<u64> {
+    if towers.towers.len() != 3 {
+        return None;
+    }
+    let TowerSelectorSet {
+        mut source,
+        mut target,
+        mut buffer,
+    } = selectors;
+    let positions = towers.positions();
+    let mut k = 0u64;
+    for height in (1..=positions.len()).rev() {
+        let position = positions[height - 1];
+        if position == source {
+            (target, buffer) = (buffer, target);
+        } else if position == target {
+            k = k.checked_add(half_moves(height)?.checked_add(1)?)?;
+            (source, buffer) = (buffer, source);
+        } else {
+            // The recursive solution never leaves a disk on its buffer.
+            return None;
+        }
+    }
+    Some(k)
+}
+
+/// Split points for the Frame–Stewart algorithm, which moves a tower across
+/// any number of pegs by first setting aside its top `split` disks using every
+/// peg, then moving the remaining disks using every peg but the one holding the
+/// set-aside disks, and finally restacking the set-aside disks on top.  The
+/// best split is found by trying them all, and is known to be optimal for four
+/// pegs.  With three pegs, the best split is always one less than the height,
+/// and the algorithm reduces to `solve`.
+pub struct FrameStewart {
+    /// The best number of disks to set aside for each peg count and height.
+    splits: Vec<Vec<usize>>,
+}
+
+impl FrameStewart {
+    /// Tabulates split points for up to the specified number of pegs and
+    /// disks.
+    pub fn new(pegs: usize, height: usize) -> FrameStewart {
+        // The fewest moves for each peg count and height, saturating at
+        // u64::MAX when a height is impossible (or impractical) to move.
+        let mut moves = vec![vec![u64::MAX; height + 1]; pegs + 1];
+        let mut splits = vec![vec![0; height + 1]; pegs + 1];
+        for (p, row) in moves.iter_mut().enumerate().skip(1) {
+            row[0] = 0;
+            if p >= 2 && height > 0 {
+                row[1] = 1;
+            }
+        }
+        for p in 3..=pegs {
+            for n in 2..=height {
+                for split in 1..n {
+                    let total = moves[p][split]
+                        .saturating_mul(2)
+                        .saturating_add(moves[p - 1][n - split]);
+                    if total < moves[p][n] {
+                        moves[p][n] = total;
+                        splits[p][n] = split;
+                    }
+                }
+            }
+        }
+        FrameStewart { splits }
+    }
+
+    /// Moves `height` disks from the first selected tower to the second,
+    /// using any remaining selected towers as buffers.  Calls `on_move` with
+    /// each move and the towers after it.
+    pub fn solve(
+        &self,
+        towers: TowerSet,
+        height: usize,
+        selectors: &[TowerSelector],
+        on_move: &mut impl FnMut(Move, &TowerSet),
+    ) -> Result<TowerSet, IllegalMoveError> {
+        let (&source, &target, buffers) = match selectors {
+            [source, target, buffers @ ..] => (source, target, buffers),
+            _ => panic!("source and target towers should be selected"),
+        };
+        match height {
+            // BASE CASE: No disks to move.
+            0 => Ok(towers),
+            // BASE CASE: One disk to move directly.
+            1 => {
+                let mut towers = towers;
+                towers.move_disk(source, target)?;
+                on_move((source, target), &towers);
+                Ok(towers)
+            }
+            // RECURSIVE CASE
+            _ => {
+                let (&spare, others) = buffers
+                    .split_first()
+                    .expect("multiple disks should require a buffer tower");
+                let split = self.splits[selectors.len()][height];
+                let aside: Vec<_> = [source, spare, target]
+                    .into_iter()
+                    .chain(others.iter().copied())
+                    .collect();
+                let towers = self.solve(towers, split, &aside, on_move)?;
+                let rest: Vec<_> = [source, target]
+                    .into_iter()
+                    .chain(others.iter().copied())
+                    .collect();
+                let towers = self.solve(towers, height - split, &rest, on_move)?;
+                let back: Vec<_> = [spare, target, source]
+                    .into_iter()
+                    .chain(others.iter().copied())
+                    .collect();
+                self.solve(towers, split, &back, on_move)
+            }
+        }
+    }
+}
+
+/// A move of the top disk of one tower to another, as (source, target).
+pub type Move = (TowerSelector, TowerSelector);
+
+/// Returns the third of three towers, given the other two.
+fn third(a: TowerSelector, b: TowerSelector) -> TowerSelector {
+    TowerSelector(3 - a.0 - b.0)
+}
+
+/// Returns the fewest moves needed to gather the disks at the specified
+/// positions (indexed by radius minus one) onto the target tower, or None if
+/// the count does not fit in a u64, as it may not for more than 64 disks.
+pub fn distance_to_tower(positions: &[TowerSelector], target: TowerSelector) -> Option<u64> {
+    match positions.split_last() {
+        None => Some(0), // BASE CASE
+        Some((&largest, rest)) if largest == target => distance_to_tower(rest, target),
+        Some((&largest, rest)) => {
+            // Everything above the largest disk must first be moved out of
+            // its way, after which the largest disk moves once, and then the
+            // rest of the disks move as a complete tower.
+            let tower = 1u64.checked_shl(u32::try_from(rest.len()).ok()?)?;
+            distance_to_tower(rest, third(largest, target))?.checked_add(tower)
+        }
+    }
+}
+
+/// Appends the fewest moves needed to gather the disks at the specified
+/// positions (indexed by radius minus one) onto the target tower.
+fn push_gather_moves(positions: &[TowerSelector], target: TowerSelector, moves: &mut Vec<Move>) {
+    match positions.split_last() {
+        None => {} // BASE CASE
+        Some((&largest, rest)) if largest == target => push_gather_moves(rest, target, moves),
+        Some((&largest, rest)) => {
+            let buffer = third(largest, target);
+            push_gather_moves(rest, buffer, moves);
+            moves.push((largest, target));
+            moves.extend(self::moves(
+                rest.len(),
+                TowerSelectorSet::from_parts(buffer, target, largest),
+            ));
+        }
+    }
+}
+
+/// Returns the first of the fewest moves needed to gather the disks at the
+/// specified positions (indexed by radius minus one) onto the target tower, or
+/// None if they are already there.  Unlike `push_gather_moves`, this takes
+/// time proportional to the number of disks, not the number of moves.
+pub fn first_gather_move(positions: &[TowerSelector], target: TowerSelector) -> Option<Move> {
+    match positions.split_last() {
+        None => None, // BASE CASE
+        Some((&largest, rest)) if largest == target => first_gather_move(rest, target),
+        Some((&largest, rest)) => {
+            // Unless the smaller disks must first get out of the way, the
+            // largest disk moves right away.
+            first_gather_move(rest, third(largest, target)).or(Some((largest, target)))
+        }
+    }
+}
+
+/// Appends the fewest moves needed to spread a complete tower from the source
+/// tower to the specified positions.  These are the moves that would gather
+/// the disks from those positions, backwards.
+fn push_spread_moves(source: TowerSelector, positions: &[TowerSelector], moves: &mut Vec<Move>) {
+    let start = moves.len();
+    push_gather_moves(positions, source, moves);
+    moves[start..].reverse();
+    for (from, to) in &mut moves[start..] {
+        std::mem::swap(from, to);
+    }
+}
+
+/// Appends the fewest moves needed to rearrange disks from one set of
+/// positions to another, each indexed by radius minus one.
+fn push_rearrange_moves(from: &[TowerSelector], to: &[TowerSelector], moves: &mut Vec<Move>) {
+    let (Some((&source, from_rest)), Some((&target, to_rest))) =
+        (from.split_last(), to.split_last())
+    else {
+        return; // BASE CASE: No disks to move.
+    };
+    if source == target {
+        // The largest disk is already in place, and never needs to move.
+        return push_rearrange_moves(from_rest, to_rest, moves);
+    }
+    // The largest disk must move, and an optimal solution moves it either
+    // once, directly to its target, or twice, by way of the third tower.  In
+    // the latter case, the smaller disks first gather on the target tower,
+    // then as a complete tower move to the source tower.
+    // Counts too large for a u64 are too large to list anyway.
+    let buffer = third(source, target);
+    let once = distance_to_tower(from_rest, buffer)
+        .zip(distance_to_tower(to_rest, buffer))
+        .map_or(u64::MAX, |(gather, spread)| gather.saturating_add(spread));
+    let twice = distance_to_tower(from_rest, target)
+        .zip(distance_to_tower(to_rest, source))
+        .map_or(u64::MAX, |(gather, spread)| {
+            let tower = 1u64.checked_shl(from_rest.len() as u32).unwrap_or(u64::MAX);
+            gather.saturating_add(tower).saturating_add(spread)
+        });
+    if once <= twice {
+        push_gather_moves(from_rest, buffer, moves);
+        moves.push((source, target));
+        push_spread_moves(buffer, to_rest, moves);
+    } else {
+        push_gather_moves(from_rest, target, moves);
+        moves.push((source, buffer));
+        moves.extend(self::moves(
+            from_rest.len(),
+            TowerSelectorSet::from_parts(target, source, buffer),
+        ));
+        moves.push((buffer, target));
+        push_spread_moves(source, to_rest, moves);
+    }
+}
+
+/// Returns the fewest moves that gather every disk onto the target tower,
+/// starting from any legal arrangement of three towers.
+///
+/// ```
+/// use part3::hanoi::{solve_from, TowerSelector, TowerSet};
+///
+/// let (a, b, c) = (TowerSelector::A, TowerSelector::B, TowerSelector::C);
+/// // Disk 1 is on tower C, and disk 2 on tower A.
+/// let towers = TowerSet::from_positions(3, &[c, a]);
+/// assert_eq!(solve_from(&towers, b), [(a, b), (c, b)]);
+/// ```
+pub fn solve_from(towers: &TowerSet, target: TowerSelector) -> Vec<Move> {
+    let goal = vec![target; towers.disks().count()];
+    solve_between(towers, &TowerSet::from_positions(3, &goal))
+}
+
+/// Returns the fewest moves that turn one legal arrangement of three towers
+/// into another having the same disks.
+pub fn solve_between(from: &TowerSet, to: &TowerSet) -> Vec<Move> {
+    assert_eq!(from.towers.len(), 3, "solve_between requires three towers");
+    assert_eq!(to.towers.len(), 3, "solve_between requires three towers");
+    let (from, to) = (from.positions(), to.positions());
+    assert_eq!(
+        from.len(),
+        to.len(),
+        "tower sets should have the same disks"
+    );
+    let mut moves = Vec::new();
+    push_rearrange_moves(&from, &to, &mut moves);
+    moves
+}
+
+/// An interactive game in progress, which remembers its moves so they can be
+/// counted, undone, and redone.
+pub struct Session {
+    /// The towers as they stand now.
+    pub towers: TowerSet,
+    /// The tower on which the player must gather every disk.
+    pub target: TowerSelector,
+    /// The moves made so far, oldest first.
+    pub history: Vec<Move>,
+    /// Moves that were undone, most recently undone last.  Any new move
+    /// clears them.
+    undone: Vec<Move>,
+    /// The fewest moves that win the game from its start, if known.
+    pub optimal: Option<u64>,
+}
+
+impl Session {
+    /// Starts a game from the towers, to be won by gathering every disk on the
+    /// target tower.
+    pub fn new(towers: TowerSet, target: TowerSelector) -> Session {
+        let optimal = (towers.towers.len() == 3)
+            .then(|| distance_to_tower(&towers.positions(), target))
+            .flatten();
+        Session {
+            towers,
+            target,
+            history: Vec::new(),
+            undone: Vec::new(),
+            optimal,
+        }
+    }
+
+    /// Resumes a saved game, which may be undone back to its start.
+    pub fn from_saved(saved: SavedGame) -> Session {
+        let mut session = Session::new(saved.start(), saved.target);
+        for (source, target) in saved.history {
+            session
+                .play(source, target)
+                .expect("saved moves should have been checked when parsed");
+        }
+        session
+    }
+
+    /// Returns the game in a form that can be saved.
+    pub fn to_saved(&self) -> SavedGame {
+        SavedGame {
+            towers: self.towers.clone(),
+            target: self.target,
+            history: self.history.clone(),
+        }
+    }
+
+    /// Resumes a game saved in a file.
+    pub fn load(path: &Path) -> Result<Session, Box<dyn Error>> {
+        let text = std::fs::read_to_string(path)?;
+        let saved = text
+            .parse::<SavedGame>()
+            .map_err(|err| format!("{}:{err}", path.display()))?;
+        Ok(Session::from_saved(saved))
+    }
+
+    /// Saves the game in a file, from which it can be resumed with `load`.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        std::fs::write(path, self.to_saved().to_string())
+    }
+
+    /// Moves the top disk of the source tower onto the target tower, unless
+    /// doing so would break the rules of the puzzle.
+    pub fn play(
+        &mut self,
+        source: TowerSelector,
+        target: TowerSelector,
+    ) -> Result<(), IllegalMoveError> {
+        self.towers.move_disk(source, target)?;
+        self.history.push((source, target));
+        self.undone.clear();
+        Ok(())
+    }
+
+    /// Takes back the latest move, returning it, or None if there are no moves
+    /// to take back.
+    pub fn undo(&mut self) -> Option<Move> {
+        let (source, target) = self.history.pop()?;
+        self.towers
+            .move_disk(target, source)
+            .expect("reversing a legal move should be legal");
+        self.undone.push((source, target));
+        Some((source, target))
+    }
+
+    /// Makes the most recently undone move again, returning it, or None if
+    /// there are no moves to redo.
+    pub fn redo(&mut self) -> Option<Move> {
+        let (source, target) = self.undone.pop()?;
+        self.towers
+            .move_disk(source, target)
+            .expect("redoing an undone move should be legal");
+        self.history.push((source, target));
+        Some((source, target))
+    }
+
+    /// Returns the first move of the shortest path from here to a win, if
+    /// there are three towers and the game is not yet won.
+    pub fn hint(&self) -> Option<Move> {
+        if self.towers.towers.len() != 3 {
+            return None;
+        }
+        first_gather_move(&self.towers.positions(), self.target)
+    }
+
+    /// Returns whether every disk is on the target tower.
+    pub fn is_won(&self) -> bool {
+        self.towers[self.target].disks.len() == self.towers.disks().count()
+    }
+
+    /// Returns a line giving the number of moves so far, and the fewest that
+    /// win, if known.
+    pub fn progress(&self) -> String {
+        match self.optimal {
+            Some(optimal) => format!("Moves so far: {} / optimal {optimal}", self.history.len()),
+            None => format!("Moves so far: {}", self.history.len()),
+        }
+    }
+
+    /// Returns a score out of 100: the fewest moves that win as a percentage
+    /// of the moves made, or 0 if the game is not won.
+    pub fn score(&self) -> u64 {
+        self.score_with_illegal(0)
+    }
+
+    /// Returns the score, counting each illegal move tried as a move made.
+    fn score_with_illegal(&self, illegal: u64) -> u64 {
+        let moves = self.history.len() as u64 + illegal;
+        match moves {
+            _ if !self.is_won() => 0,
+            0 => 100,
+            _ => 100 * self.optimal.unwrap_or_default() / moves,
+        }
+    }
+
+    /// Returns a sentence giving the outcome of the game, and how it compares
+    /// with the optimal solution.
+    pub fn summary(&self) -> String {
+        let moves = self.history.len();
+        let outcome = if self.is_won() {
+            format!("Solved in {moves} moves")
+        } else {
+            format!("Stopped after {moves} moves")
+        };
+        match self.optimal {
+            Some(optimal) if self.is_won() && moves as u64 == optimal => {
+                format!("{outcome}, which is optimal!")
+            }
+            Some(optimal) => format!("{outcome}; the optimal solution takes {optimal}."),
+            None => format!("{outcome}."),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const A: TowerSelector = TowerSelector::A;
+    const B: TowerSelector = TowerSelector::B;
+    const C: TowerSelector = TowerSelector::C;
+
+    #[test]
+    fn test_move_disk() {
+        let mut towers = TowerSet::with_pegs(3, 2);
+        assert_eq!(towers.move_disk(A, A), Err(IllegalMoveError::SameTower(A)));
+        assert_eq!(towers.move_disk(B, C), Err(IllegalMoveError::EmptyTower(B)));
+        assert_eq!(towers.move_disk(A, B), Ok(()));
+        assert_eq!(
+            towers.move_disk(A, B),
+            Err(IllegalMoveError::OversizeDisk {
+                source: A,
+                target: B,
+                radius: 2,
+                below: 1,
+            })
+        );
+        assert_eq!(towers.move_disk(A, C), Ok(()));
+        assert_eq!(towers.move_disk(B, C), Ok(()));
+        assert_eq!(
+            towers.move_disk_with(&Rules::Cyclic, C, B),
+            Err(IllegalMoveError::ForbiddenMove {
+                source: C,
+                target: B,
+            })
+        );
+        assert_eq!(
+            towers.move_disk(A, TowerSelector(3)),
+            Err(IllegalMoveError::NoSuchTower(TowerSelector(3)))
+        );
+        assert_eq!(
+            towers,
+            TowerSet {
+                towers: vec![Tower::default(), Tower::default(), Tower::with_disks(2)],
+            }
+        );
+    }
+
+    #[test]
+    fn test_solve() {
+        // Since move_disk rejects illegal moves, and solve propagates any
+        // error, an Ok result shows the solver never broke the rules.
+        for n in 0..10 {
+            let want = Ok(TowerSet {
+                towers: vec![Tower::default(), Tower::with_disks(n), Tower::default()],
+            });
+            let got = solve(
+                TowerSet::with_pegs(3, n),
+                n,
+                TowerSelectorSet::new(),
+                &mut |_, _| {},
+            );
+            assert_eq!(got, want);
+        }
+    }
+
+    #[test]
+    fn test_moves() {
+        let got: Vec<_> = moves(3, TowerSelectorSet::new()).collect();
+        let want = [(A, B), (A, C), (B, C), (A, B), (C, A), (C, B), (A, B)];
+        assert_eq!(got, want);
+        for n in 0..16 {
+            assert_eq!(moves(n, TowerSelectorSet::new()).count(), (1 << n) - 1);
+        }
+    }
+
+    #[test]
+    fn test_moves_is_lazy() {
+        // Collecting 2^40 - 1 moves would exhaust memory, but taking a few
+        // should be quick, wherever they are in the sequence.
+        let mut moves = moves(40, TowerSelectorSet::new());
+        let got: Vec<_> = moves.by_ref().take(3).collect();
+        assert_eq!(got, [(A, C), (A, B), (C, B)]);
+        assert_eq!(moves.nth(1 << 20), Some((B, A)));
+    }
+
+    /// Returns every arrangement of n disks on three towers, as positions.
+    fn all_positions(n: usize) -> Vec<Vec<TowerSelector>> {
+        (0..3usize.pow(n as u32))
+            .map(|mut code| {
+                (0..n)
+                    .map(|_| {
+                        let selector = TowerSelector(code % 3);
+                        code /= 3;
+                        selector
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Returns the distance between two arrangements by breadth-first search.
+    fn bfs_distance(from: &[TowerSelector], to: &[TowerSelector]) -> usize {
+        let mut seen = vec![from.to_vec()];
+        let mut frontier = vec![from.to_vec()];
+        for distance in 0.. {
+            if frontier.iter().any(|positions| positions == to) {
+                return distance;
+            }
+            let mut next = Vec::new();
+            for positions in frontier {
+                let towers = TowerSet::from_positions(3, &positions);
+                for source in towers.selectors() {
+                    for target in towers.selectors() {
+                        let mut towers = TowerSet::from_positions(3, &positions);
+                        if towers.move_disk(source, target).is_ok() {
+                            let positions = towers.positions();
+                            if !seen.contains(&positions) {
+                                seen.push(positions.clone());
+                                next.push(positions);
+                            }
+                        }
+                    }
+                }
+            }
+            frontier = next;
+        }
+        unreachable!()
+    }
+
+    /// Compares the towers, drawn to fit the specified width (if any), with
+    /// the expected lines.
+    fn assert_display(towers: &TowerSet, width: Option<usize>, want: &[&str]) {
+        let display = TowerSetDisplay::new(towers);
+        let got = match width {
+            Some(width) => display.fit_width(width).to_string(),
+            None => display.to_string(),
+        };
+        assert_eq!(got, want.join("\n"));
+    }
+
+    #[test]
+    fn test_display_small() {
+        let want = ["  ||   ||   || ", " @_1@  ||   || ", "   A    B    C "];
+        assert_display(&TowerSet::with_pegs(3, 1), None, &want);
+        let want = [
+            "          ||                   ||                   ||         ",
+            "         @_1@                  ||                   ||         ",
+            "        @@_2@@                 ||                   ||         ",
+            "       @@@_3@@@                ||                   ||         ",
+            "      @@@@_4@@@@               ||                   ||         ",
+            "     @@@@@_5@@@@@              ||                   ||         ",
+            "    @@@@@@_6@@@@@@             ||                   ||         ",
+            "   @@@@@@@_7@@@@@@@            ||                   ||         ",
+            "  @@@@@@@@_8@@@@@@@@           ||                   ||         ",
+            " @@@@@@@@@_9@@@@@@@@@          ||                   ||         ",
+            "           A                    B                    C         ",
+        ];
+        assert_display(&TowerSet::with_pegs(3, 9), None, &want);
+    }
+
+    #[test]
+    fn test_display_large() {
+        let selectors = TowerSelectorSet::new();
+        let want = [
+            "           ||                     ||                     ||          ",
+            "           ||                     ||                     ||          ",
+            "           ||                     ||                     ||          ",
+            "           ||                     ||                     ||          ",
+            "           ||                     ||                     ||          ",
+            "           ||                     ||                     ||          ",
+            "           ||                     ||                     ||          ",
+            "          @_1@                    ||                 @@@@_4@@@@      ",
+            "         @@_2@@                   ||                @@@@@_5@@@@@     ",
+            "        @@@_3@@@           @@@@@@@_7@@@@@@@      @@@@@@@@_8@@@@@@@@  ",
+            "     @@@@@@_6@@@@@@     @@@@@@@@@@10@@@@@@@@@@  @@@@@@@@@_9@@@@@@@@@ ",
+            "            A                      B                      C          ",
+        ];
+        assert_display(&state_after(10, 600, selectors).unwrap(), None, &want);
+        let want = [
+            "            ||                       ||                       ||           ",
+            "            ||                       ||                       ||           ",
+            "            ||                       ||                       ||           ",
+            "            ||                       ||                       ||           ",
+            "            ||                       ||                       ||           ",
+            "            ||                       ||                       ||           ",
+            "            ||                       ||                       ||           ",
+            "            ||                       ||                       ||           ",
+            "            ||                       ||                       ||           ",
+            "            ||                       ||                       ||           ",
+            "            ||                       ||                       ||           ",
+            "            ||                       ||                       ||           ",
+            "            ||                       ||                       ||           ",
+            "           @_1@                      ||                   @@@@_6@@@@       ",
+            "          @@_2@@                     ||                   @@@@_7@@@@       ",
+            "          @@_3@@                     ||                  @@@@@_8@@@@@      ",
+            "         @@@_4@@@                    ||                  @@@@@_9@@@@@      ",
+            "         @@@_5@@@             @@@@@@@11@@@@@@@          @@@@@@10@@@@@@     ",
+            "    @@@@@@@@14@@@@@@@@        @@@@@@@12@@@@@@@        @@@@@@@@13@@@@@@@@   ",
+            "   @@@@@@@@@15@@@@@@@@@    @@@@@@@@@@17@@@@@@@@@@    @@@@@@@@@16@@@@@@@@@  ",
+            " @@@@@@@@@@@20@@@@@@@@@@@  @@@@@@@@@@18@@@@@@@@@@  @@@@@@@@@@@19@@@@@@@@@@@",
+            "             A                        B                        C           ",
+        ];
+        let towers = state_after(20, 300_000, selectors).unwrap();
+        assert_display(&towers, Some(80), &want);
+    }
+
+    #[test]
+    fn test_display_aligned() {
+        for n in 10..=64 {
+            let k = (1 << (n - 1)) + n as u64;
+            let towers = state_after(n, k, TowerSelectorSet::new()).unwrap();
+            for width in [None, Some(80)] {
+                let mut display = TowerSetDisplay::new(&towers);
+                if let Some(width) = width {
+                    display = display.fit_width(width);
+                }
+                let text = display.to_string();
+                let widths: Vec<_> = text.lines().map(|line| line.chars().count()).collect();
+                assert!(widths.iter().all(|&w| w == widths[0]), "{n} disks: {text}");
+                assert!(width.is_none_or(|width| widths[0] <= width));
+            }
+        }
+    }
+
+    #[test]
+    fn test_positions() {
+        for positions in all_positions(4) {
+            assert_eq!(
+                TowerSet::from_positions(3, &positions).positions(),
+                positions
+            );
+        }
+    }
+
+    #[test]
+    fn test_iterative_solvers() {
+        for n in 0..=12 {
+            for selectors in [
+                TowerSelectorSet::new(),
+                TowerSelectorSet::from_parts(A, C, B),
+                TowerSelectorSet::from_parts(C, A, B),
+            ] {
+                let want: Vec<_> = moves(n, selectors).collect();
+                assert_eq!(moves_iterative(n, selectors), want);
+                assert_eq!(moves_bitwise(n, selectors).collect::<Vec<_>>(), want);
+                assert_eq!(moves_emulate_recursion(n, selectors), want);
+            }
+        }
+    }
+
+    #[test]
+    fn test_closed_form_queries() {
+        let selectors = TowerSelectorSet::new();
+        for n in 0..=12 {
+            let mut towers = TowerSet::with_pegs(3, n);
+            assert_eq!(state_after(n, 0, selectors).as_ref(), Some(&towers));
+            assert_eq!(moves_made(&towers, selectors), Some(0));
+            assert_eq!(nth_move(n, 0, selectors), None);
+            let mut k = 0;
+            solve(TowerSet::with_pegs(3, n), n, selectors, &mut |_, want| {
+                let (source, target) = nth_move(n, k + 1, selectors).unwrap();
+                towers.move_disk(source, target).unwrap();
+                assert_eq!(&towers, want);
+                k += 1;
+                assert_eq!(state_after(n, k, selectors).as_ref(), Some(want));
+                assert_eq!(moves_made(want, selectors), Some(k));
+            })
+            .unwrap();
+            assert_eq!(k, (1 << n) - 1);
+            assert_eq!(nth_move(n, k + 1, selectors), None);
+            assert_eq!(state_after(n, k + 1, selectors), None);
+        }
+    }
+
+    #[test]
+    fn test_closed_form_queries_off_path() {
+        // The recursive solution never leaves a disk alone on the buffer.
+        let towers = TowerSet::from_positions(3, &[C]);
+        assert_eq!(moves_made(&towers, TowerSelectorSet::new()), None);
+        let towers = TowerSet::from_positions(3, &[A, B, A]);
+        assert_eq!(moves_made(&towers, TowerSelectorSet::new()), None);
+    }
+
+    #[test]
+    fn test_half_moves() {
+        assert_eq!(half_moves(0), Some(0));
+        assert_eq!(half_moves(1), Some(0));
+        assert_eq!(half_moves(3), Some(3));
+        assert_eq!(half_moves(64), Some((1 << 63) - 1));
+        assert_eq!(half_moves(65), None);
+        assert_eq!(half_moves(usize::MAX), None);
+    }
+
+    #[test]
+    fn test_closed_form_queries_large() {
+        let selectors = TowerSelectorSet::new();
+        let last = u64::MAX; // 2^64 - 1 moves for 64 disks.
+        assert_eq!(nth_move(64, 1 << 63, selectors), Some((A, B)));
+        assert_eq!(nth_move(64, last, selectors), Some((C, B)));
+        // With 64 disks, the towers are numbered A, B, C.
+        assert_eq!(bitwise_move(last), (2, 1));
+        assert_eq!(bitwise_move(1 << 63), (0, 1));
+        assert_eq!(nth_move(100, last, selectors), Some((C, B)));
+        let towers = state_after(64, last, selectors).unwrap();
+        assert_eq!(towers[B], Tower::with_disks(64));
+        assert_eq!(moves_made(&towers, selectors), Some(last));
+        let towers = state_after(80, 1 << 40, selectors).unwrap();
+        assert_eq!(moves_made(&towers, selectors), Some(1 << 40));
+    }
+
+    #[test]
+    fn test_session() {
+        let mut session = Session::new(TowerSet::with_pegs(3, 2), B);
+        assert_eq!(session.optimal, Some(3));
+        assert_eq!(session.undo(), None);
+        assert!(session.play(A, B).is_ok());
+        assert!(session.play(A, B).is_err());
+        assert_eq!(session.history, [(A, B)]);
+        assert_eq!(session.undo(), Some((A, B)));
+        assert_eq!(session.towers, TowerSet::with_pegs(3, 2));
+        assert_eq!(session.redo(), Some((A, B)));
+        assert_eq!(session.redo(), None);
+        assert_eq!(session.undo(), Some((A, B)));
+        assert!(session.play(A, C).is_ok());
+        assert_eq!(session.redo(), None); // A new move clears the undone moves.
+        assert_eq!(session.progress(), "Moves so far: 1 / optimal 3");
+        assert_eq!(session.score(), 0);
+        assert!(session.play(C, A).is_ok());
+        while let Some((source, target)) = session.hint() {
+            assert!(!session.is_won());
+            session.play(source, target).unwrap();
+        }
+        assert!(session.is_won());
+        assert_eq!(session.history.len(), 5);
+        assert_eq!(
+            session.summary(),
+            "Solved in 5 moves; the optimal solution takes 3."
+        );
+        assert_eq!(session.score(), 60);
+    }
+
+    #[test]
+    fn test_session_hint() {
+        for n in 0..5 {
+            for positions in all_positions(n) {
+                let towers = TowerSet::from_positions(3, &positions);
+                let want = solve_from(&towers, C).first().copied();
+                assert_eq!(Session::new(towers, C).hint(), want);
+            }
+        }
+        let session = Session::new(TowerSet::with_pegs(3, 6), B);
+        assert_eq!(session.optimal, Some(63));
+    }
+
+    #[test]
+    fn test_session_too_many_disks() {
+        // 2^64 - 1 moves still fit in a u64, but 2^65 - 1 do not.
+        let session = Session::new(TowerSet::with_pegs(3, 64), B);
+        assert_eq!(session.optimal, Some(u64::MAX));
+        let session = Session::new(TowerSet::with_pegs(3, 70), B);
+        assert_eq!(session.optimal, None);
+        assert_eq!(session.progress(), "Moves so far: 0");
+        assert_eq!(session.hint(), Some((A, C)));
+    }
+
+    #[test]
+    fn test_session_save() {
+        let mut session = Session::new(TowerSet::with_pegs(3, 3), C);
+        session.play(A, C).unwrap();
+        session.play(A, B).unwrap();
+        session.play(C, A).unwrap();
+        session.undo();
+        let text = session.to_saved().to_string();
+        assert_eq!(text, "pegs 3\ntarget C\nA 3\nB 2\nC 1\nmoves AC AB\n");
+        let resumed = Session::from_saved(text.parse().unwrap());
+        assert_eq!(resumed.towers, session.towers);
+        assert_eq!(resumed.history, session.history);
+        assert_eq!(resumed.optimal, Some(7));
+    }
+
+    #[test]
+    fn test_solve_from() {
+        for n in 0..5 {
+            for positions in all_positions(n) {
+                let mut towers = TowerSet::from_positions(3, &positions);
+                let moves = solve_from(&towers, B);
+                assert_eq!(moves.len(), bfs_distance(&positions, &vec![B; n]));
+                for (source, target) in moves {
+                    towers.move_disk(source, target).unwrap();
+                }
+                let mut want = TowerSet::with_pegs(3, 0);
+                want[B] = Tower::with_disks(n);
+                assert_eq!(towers, want);
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_from_complete_tower() {
+        for n in 0..10 {
+            let want = solve(
+                TowerSet::with_pegs(3, n),
+                n,
+                TowerSelectorSet::new(),
+                &mut |_, _| {},
+            )
+            .unwrap();
+            let mut towers = TowerSet::with_pegs(3, n);
+            let moves = solve_from(&towers, B);
+            assert_eq!(moves.len(), (1 << n) - 1);
+            for (source, target) in moves {
+                towers.move_disk(source, target).unwrap();
+            }
+            assert_eq!(towers, want);
+        }
+    }
+
+    #[test]
+    fn test_solve_between() {
+        for n in 0..4 {
+            for from in all_positions(n) {
+                for to in all_positions(n) {
+                    let mut towers = TowerSet::from_positions(3, &from);
+                    let want = TowerSet::from_positions(3, &to);
+                    let moves = solve_between(&towers, &want);
+                    assert_eq!(moves.len(), bfs_distance(&from, &to), "{from:?} -> {to:?}");
+                    for (source, target) in moves {
+                        towers.move_disk(source, target).unwrap();
+                    }
+                    assert_eq!(towers, want);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_tower_selector() {
+        assert_eq!("A".parse::<TowerSelector>().ok(), Some(A));
+        assert_eq!("D".parse::<TowerSelector>().ok(), Some(TowerSelector(3)));
+        assert_eq!("Z".parse::<TowerSelector>().ok(), Some(TowerSelector(25)));
+        for bad in ["", "a", "AB", "1"] {
+            assert!(bad.parse::<TowerSelector>().is_err());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "tower 26 has no letter")]
+    fn test_label_out_of_range() {
+        assert_eq!(TowerSelector(25).label(), 'Z');
+        TowerSelector(TowerSelector::MAX).label();
+    }
+
+    /// Runs the Frame–Stewart solver from the first peg to the second, checking
+    /// that it ends with every disk on the target and returning the number of
+    /// moves made.
+    fn frame_stewart_moves(pegs: usize, height: usize) -> u64 {
+        let selectors: Vec<_> = (0..pegs).map(TowerSelector).collect();
+        let mut moves = 0;
+        let got = FrameStewart::new(pegs, height)
+            .solve(
+                TowerSet::with_pegs(pegs, height),
+                height,
+                &selectors,
+                &mut |_, _| moves += 1,
+            )
+            .expect("solver should make only legal moves");
+        let mut want = TowerSet::with_pegs(pegs, 0);
+        want.towers[1] = Tower::with_disks(height);
+        assert_eq!(got, want);
+        moves
+    }
+
+    #[test]
+    fn test_frame_stewart_three_pegs() {
+        for n in 0..12 {
+            let want = (1 << n) - 1;
+            assert_eq!(frame_stewart_moves(3, n), want);
+        }
+    }
+
+    #[test]
+    fn test_frame_stewart_four_pegs() {
+        // See https://oeis.org/A007664.
+        let wants = [0, 1, 3, 5, 9, 13, 17, 25, 33, 41, 49, 65, 81, 97, 113];
+        for (n, want) in wants.into_iter().enumerate() {
+            assert_eq!(frame_stewart_moves(4, n), want);
+        }
+    }
+
+    #[test]
+    fn test_frame_stewart_five_pegs() {
+        // See https://oeis.org/A007665.
+        let wants = [0, 1, 3, 5, 7, 11, 15, 19, 23, 27, 31, 39, 47];
+        for (n, want) in wants.into_iter().enumerate() {
+            assert_eq!(frame_stewart_moves(5, n), want);
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! A notation for recording games as lists of moves.  Each move is written as
+//! the labels of its source and target towers, such as `AB` for a move from
+//! tower A to tower B.  Moves are separated by whitespace, and may be preceded
+//! by their numbers, counting from one, such as `1. AC 2. AB 3. CB`.  A `#`
+//! starts a comment, which runs to the end of the line.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+
+use super::{IllegalMoveError, Move, ParseTextError, Rules, TowerSelector, TowerSet};
+
+/// Parses a move such as `AB`, or returns None if it is not a move.
+pub fn parse_move(s: &str) -> Option<Move> {
+    let source = s.get(..1)?.parse().ok()?;
+    let target = s.get(1..)?.parse().ok()?;
+    Some((source, target))
+}
+
+/// Parses a list of moves, checking that any move numbers are in sequence.
+///
+/// ```
+/// use part3::hanoi::notation::parse_moves;
+/// use part3::hanoi::TowerSelector;
+///
+/// let (a, b, c) = (TowerSelector::A, TowerSelector::B, TowerSelector::C);
+/// assert_eq!(parse_moves("1. AC 2. AB # and so on"), Ok(vec![(a, c), (a, b)]));
+/// assert_eq!(
+///     parse_moves("1. AC 3. AB").unwrap_err().to_string(),
+///     "1:7: 3.: expected move 2"
+/// );
+/// ```
+pub fn parse_moves(s: &str) -> Result<Vec<Move>, ParseTextError> {
+    let mut moves = Vec::new();
+    for (index, line) in s.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut offset = 0;
+        for word in line.split_whitespace() {
+            // Words are found in order, so each one is after the last.
+            offset += line[offset..].find(word).expect("word should be in line");
+            let error =
+                |column: usize, message: String| ParseTextError::at(index, line, column, message);
+            // Split off the move number, if any.
+            let (number, text, column) = match word.split_once('.') {
+                Some((number, text)) => (Some(number), text, offset + number.len() + 1),
+                None => (None, word, offset),
+            };
+            if let Some(number) = number {
+                let want = moves.len() + 1;
+                match number.parse::<usize>() {
+                    Ok(got) if got == want => {}
+                    _ => return Err(error(offset, format!("{number}.: expected move {want}"))),
+                }
+            }
+            if !text.is_empty() {
+                let next =
+                    parse_move(text).ok_or_else(|| error(column, format!("{text}: bad move")))?;
+                moves.push(next);
+            }
+            offset += word.len();
+        }
+    }
+    Ok(moves)
+}
+
+/// Writes moves in numbered notation as they are made.
+pub struct MoveRecorder<W: Write> {
+    out: W,
+    count: usize,
+}
+
+impl<W: Write> MoveRecorder<W> {
+    /// Starts a list of moves in `out`.
+    pub fn new(out: W) -> MoveRecorder<W> {
+        MoveRecorder { out, count: 0 }
+    }
+
+    /// Writes a line of text as a comment.
+    pub fn comment(&mut self, text: &str) -> io::Result<()> {
+        writeln!(self.out, "# {text}")
+    }
+
+    /// Writes the next move, with its number.
+    pub fn record(&mut self, (source, target): Move) -> io::Result<()> {
+        self.count += 1;
+        writeln!(self.out, "{}. {source}{target}", self.count)
+    }
+
+    /// Flushes the list, and returns where it was written.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// Reasons a list of moves fails to solve a puzzle.
+#[derive(Debug, Eq, PartialEq)]
+pub enum CheckMovesError {
+    /// The move with this number, counting from one, breaks the rules.
+    IllegalMove {
+        /// The number of the move.
+        number: usize,
+        /// The move itself.
+        next: Move,
+        /// Why the move breaks the rules.
+        error: IllegalMoveError,
+    },
+    /// Every move is legal, but they leave some disks off the target.
+    Unfinished {
+        /// The number of moves made.
+        moves: usize,
+        /// The tower that should hold every disk.
+        target: TowerSelector,
+    },
+}
+
+impl fmt::Display for CheckMovesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckMovesError::IllegalMove {
+                number,
+                next: (source, target),
+                error,
+            } => write!(f, "move {number} ({source}{target}): {error}"),
+            CheckMovesError::Unfinished { moves, target } => write!(
+                f,
+                "after {moves} moves, not every disk is on tower {target}"
+            ),
+        }
+    }
+}
+
+impl Error for CheckMovesError {}
+
+/// Makes the moves, checking that each is legal under the rules and that they
+/// end with every disk on the target tower.  Returns the final towers.
+pub fn check_moves(
+    mut towers: TowerSet,
+    moves: &[Move],
+    rules: &Rules,
+    target: TowerSelector,
+) -> Result<TowerSet, CheckMovesError> {
+    for (index, &(source, to)) in moves.iter().enumerate() {
+        towers
+            .move_disk_with(rules, source, to)
+            .map_err(|error| CheckMovesError::IllegalMove {
+                number: index + 1,
+                next: (source, to),
+                error,
+            })?;
+    }
+    if towers[target].disks.len() != towers.disks().count() {
+        return Err(CheckMovesError::Unfinished {
+            moves: moves.len(),
+            target,
+        });
+    }
+    Ok(towers)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const A: TowerSelector = TowerSelector::A;
+    const B: TowerSelector = TowerSelector::B;
+    const C: TowerSelector = TowerSelector::C;
+
+    #[test]
+    fn test_parse_moves() {
+        let want = [(A, C), (A, B), (C, B)];
+        assert_eq!(parse_moves("AC AB CB"), Ok(want.to_vec()));
+        assert_eq!(parse_moves("1. AC\n2. AB\n3. CB\n"), Ok(want.to_vec()));
+        assert_eq!(parse_moves("1.AC 2.AB # optimal\n3.CB"), Ok(want.to_vec()));
+        assert_eq!(
+            parse_moves("# Two disks.\n\nAC AB 3. CB"),
+            Ok(want.to_vec())
+        );
+        assert_eq!(parse_moves(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_parse_moves_errors() {
+        let error = |s| parse_moves(s).unwrap_err().to_string();
+        assert_eq!(error("AC A1"), "1:4: A1: bad move");
+        assert_eq!(error("AC\n  ab"), "2:3: ab: bad move");
+        assert_eq!(error("1. AC 3. AB"), "1:7: 3.: expected move 2");
+        assert_eq!(error("AC x.AB"), "1:4: x.: expected move 2");
+        assert_eq!(error("1.AC 2.ABC"), "1:8: ABC: bad move");
+        // Columns count characters, not bytes.
+        assert_eq!(error("1. AC\u{3000}A1"), "1:7: A1: bad move");
+    }
+
+    #[test]
+    fn test_recorder() {
+        let mut recorder = MoveRecorder::new(Vec::new());
+        recorder.comment("Two disks.").unwrap();
+        for next in [(A, C), (A, B), (C, B)] {
+            recorder.record(next).unwrap();
+        }
+        let text = String::from_utf8(recorder.finish().unwrap()).unwrap();
+        assert_eq!(text, "# Two disks.\n1. AC\n2. AB\n3. CB\n");
+        assert_eq!(parse_moves(&text), Ok(vec![(A, C), (A, B), (C, B)]));
+    }
+
+    #[test]
+    fn test_check_moves() {
+        let towers = || TowerSet::with_pegs(3, 2);
+        let want = Ok(TowerSet::from_positions(3, &[B, B]));
+        assert_eq!(
+            check_moves(towers(), &[(A, C), (A, B), (C, B)], &Rules::Classic, B),
+            want
+        );
+        assert_eq!(
+            check_moves(towers(), &[(A, C), (A, C)], &Rules::Classic, B),
+            Err(CheckMovesError::IllegalMove {
+                number: 2,
+                next: (A, C),
+                error: IllegalMoveError::OversizeDisk {
+                    source: A,
+                    target: C,
+                    radius: 2,
+                    below: 1,
+                },
+            })
+        );
+        assert_eq!(
+            check_moves(towers(), &[(A, C), (A, B)], &Rules::Classic, B),
+            Err(CheckMovesError::Unfinished {
+                moves: 2,
+                target: B,
+            })
+        );
+        assert_eq!(
+            check_moves(towers(), &[(A, C), (A, B)], &Rules::Classic, B)
+                .unwrap_err()
+                .to_string(),
+            "after 2 moves, not every disk is on tower B"
+        );
+        assert_eq!(
+            check_moves(towers(), &[(A, B), (A, C)], &Rules::Cyclic, C),
+            Err(CheckMovesError::IllegalMove {
+                number: 2,
+                next: (A, C),
+                error: IllegalMoveError::ForbiddenMove {
+                    source: A,
+                    target: C,
+                },
+            })
+        );
+    }
+}
@@ -0,0 +1,423 @@
+//! A compact state of the puzzle, for searching and hashing many states
+//! without allocating.  Since the disks on each tower must be in order, a
+//! state is fully described by the tower each disk is on.  `PackedState`
+//! keeps the tower of each disk as a fixed-width field of bits in a `u128`,
+//! smallest disk in the lowest field, which is wide enough for 64 disks on
+//! up to four towers, or 25 disks on 26 towers.
+//!
+//! Fields of bits, rather than base-k digits, let the disks on a tower be
+//! found with a few word operations, so checking a move takes constant time.
+//! States can also be ranked: numbered from 0 to k^n - 1 by reading the
+//! towers as the base-k digits of a number, for indexing dense tables.
+
+use std::error::Error;
+use std::fmt;
+
+use super::{Disk, IllegalMoveError, Move, TowerSelector, TowerSet};
+
+/// Reasons towers cannot be packed into a `PackedState`.
+#[derive(Debug, Eq, PartialEq)]
+pub enum PackStateError {
+    /// There are no towers, or more than can be labelled.
+    PegCount(usize),
+    /// A disk is on a tower beyond the number of pegs.
+    NoTower {
+        /// The number of pegs.
+        pegs: usize,
+        /// The tower that does not exist.
+        selector: TowerSelector,
+    },
+    /// There are too many disks to pack for the number of pegs.
+    TooManyDisks {
+        /// The number of pegs.
+        pegs: usize,
+        /// The number of disks.
+        disks: usize,
+    },
+    /// The rank is not less than k^n, the number of states.
+    RankOutOfRange {
+        /// The number of pegs.
+        pegs: usize,
+        /// The number of disks.
+        disks: usize,
+        /// The rank itself.
+        rank: u128,
+    },
+}
+
+impl fmt::Display for PackStateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PackStateError::PegCount(pegs) => write!(
+                f,
+                "peg count should be from 1 to {}: {pegs}",
+                TowerSelector::MAX
+            ),
+            PackStateError::NoTower { pegs, selector } => {
+                write!(f, "there is no tower {selector} among {pegs} pegs")
+            }
+            PackStateError::TooManyDisks { pegs, disks } => write!(
+                f,
+                "{disks} disks will not pack for {pegs} pegs; the most is {}",
+                PackedState::capacity(pegs)
+            ),
+            PackStateError::RankOutOfRange { pegs, disks, rank } => write!(
+                f,
+                "rank {rank} is out of range for {disks} disks on {pegs} pegs"
+            ),
+        }
+    }
+}
+
+impl Error for PackStateError {}
+
+/// The towers of every disk, packed into a single integer.  States compare
+/// by number of pegs, then number of disks, then the tower of each disk from
+/// the largest down.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct PackedState {
+    pegs: u8,
+    disks: u8,
+    fields: u128,
+}
+
+impl PackedState {
+    /// Returns the number of bits needed to hold any tower of the specified
+    /// number.
+    fn field_width(pegs: usize) -> u32 {
+        usize::BITS - (pegs.max(2) - 1).leading_zeros()
+    }
+
+    /// Returns the most disks that can be packed with the specified number
+    /// of pegs.
+    pub fn capacity(pegs: usize) -> usize {
+        (u128::BITS / PackedState::field_width(pegs)) as usize
+    }
+
+    /// Returns a state with every disk on the specified tower, if they fit.
+    pub fn new(
+        pegs: usize,
+        disks: usize,
+        selector: TowerSelector,
+    ) -> Result<PackedState, PackStateError> {
+        let mut state = PackedState::from_positions(pegs, &[])?;
+        if selector.0 >= pegs {
+            return Err(PackStateError::NoTower { pegs, selector });
+        }
+        if disks > PackedState::capacity(pegs) {
+            return Err(PackStateError::TooManyDisks { pegs, disks });
+        }
+        state.disks = disks as u8;
+        state.fields = selector.0 as u128 * state.low_bits();
+        Ok(state)
+    }
+
+    /// Returns a state with each disk on the tower given by its position,
+    /// indexed by radius minus one, if they fit on the pegs.
+    pub fn from_positions(
+        pegs: usize,
+        positions: &[TowerSelector],
+    ) -> Result<PackedState, PackStateError> {
+        let disks = positions.len();
+        if !(1..=TowerSelector::MAX).contains(&pegs) {
+            return Err(PackStateError::PegCount(pegs));
+        }
+        if disks > PackedState::capacity(pegs) {
+            return Err(PackStateError::TooManyDisks { pegs, disks });
+        }
+        let width = PackedState::field_width(pegs);
+        let mut fields = 0;
+        for (index, &selector) in positions.iter().enumerate() {
+            if selector.0 >= pegs {
+                return Err(PackStateError::NoTower { pegs, selector });
+            }
+            fields |= (selector.0 as u128) << (index as u32 * width);
+        }
+        Ok(PackedState {
+            pegs: pegs as u8,
+            disks: disks as u8,
+            fields,
+        })
+    }
+
+    /// Returns the number of pegs.
+    pub fn pegs(self) -> usize {
+        self.pegs as usize
+    }
+
+    /// Returns the number of disks.
+    pub fn disks(self) -> usize {
+        self.disks as usize
+    }
+
+    fn width(self) -> u32 {
+        PackedState::field_width(self.pegs())
+    }
+
+    /// Returns a mask with the lowest bit of each disk's field set.
+    fn low_bits(self) -> u128 {
+        let used = self.disks as u32 * self.width();
+        if used == 0 {
+            return 0;
+        }
+        let ones = u128::MAX >> (u128::BITS - used);
+        ones / ((1 << self.width()) - 1)
+    }
+
+    /// Returns a mask with the lowest bit of the field of each disk on the
+    /// specified tower set.
+    fn on_tower(self, selector: TowerSelector) -> u128 {
+        let low = self.low_bits();
+        // Fields equal to the tower become zero; collect any bit still set in
+        // each field into its lowest bit.
+        let differences = self.fields ^ (selector.0 as u128 * low);
+        let mut nonzero = differences;
+        for shift in 1..self.width() {
+            nonzero |= differences >> shift;
+        }
+        !nonzero & low
+    }
+
+    /// Returns the tower holding the disk of the specified radius.
+    pub fn tower_of(self, radius: usize) -> TowerSelector {
+        assert!((1..=self.disks()).contains(&radius), "no disk {radius}");
+        let width = self.width();
+        let field = self.fields >> ((radius - 1) as u32 * width);
+        TowerSelector((field & ((1 << width) - 1)) as usize)
+    }
+
+    /// Returns the towers of the disks, indexed by radius minus one.
+    pub fn positions(self) -> Vec<TowerSelector> {
+        (1..=self.disks())
+            .map(|radius| self.tower_of(radius))
+            .collect()
+    }
+
+    /// Returns the radius of the smallest disk on the specified tower, if
+    /// any, in constant time.
+    pub fn top(self, selector: TowerSelector) -> Option<usize> {
+        match self.on_tower(selector) {
+            0 => None,
+            mask => Some((mask.trailing_zeros() / self.width()) as usize + 1),
+        }
+    }
+
+    /// Checks whether the top disk of the source tower may be moved onto the
+    /// target tower, in constant time, and returns its radius.
+    pub fn check_move(
+        self,
+        source: TowerSelector,
+        target: TowerSelector,
+    ) -> Result<usize, IllegalMoveError> {
+        if let Some(tower) = [source, target]
+            .into_iter()
+            .find(|tower| tower.0 >= self.pegs())
+        {
+            return Err(IllegalMoveError::NoSuchTower(tower));
+        }
+        if source == target {
+            return Err(IllegalMoveError::SameTower(source));
+        }
+        let radius = self
+            .top(source)
+            .ok_or(IllegalMoveError::EmptyTower(source))?;
+        match self.top(target) {
+            Some(below) if below < radius => Err(IllegalMoveError::OversizeDisk {
+                source,
+                target,
+                radius,
+                below,
+            }),
+            _ => Ok(radius),
+        }
+    }
+
+    /// Moves the top disk of the source tower onto the target tower, unless
+    /// doing so would break the rules of the puzzle.
+    pub fn move_disk(
+        &mut self,
+        source: TowerSelector,
+        target: TowerSelector,
+    ) -> Result<(), IllegalMoveError> {
+        let radius = self.check_move(source, target)?;
+        let shift = (radius - 1) as u32 * self.width();
+        self.fields -= (source.0 as u128) << shift;
+        self.fields += (target.0 as u128) << shift;
+        Ok(())
+    }
+
+    /// Returns every legal move from this state, and the state it leads to.
+    pub fn successors(self) -> impl Iterator<Item = (Move, PackedState)> {
+        let selectors = (0..self.pegs()).map(TowerSelector);
+        selectors
+            .clone()
+            .flat_map(move |source| selectors.clone().map(move |target| (source, target)))
+            .filter_map(move |(source, target)| {
+                let mut next = self;
+                next.move_disk(source, target).ok()?;
+                Some(((source, target), next))
+            })
+    }
+
+    /// Returns the number of this state among all states with the same pegs
+    /// and disks, reading the tower of each disk, smallest first, as the
+    /// digits of a base-k number, least significant first.
+    pub fn rank(self) -> u128 {
+        (1..=self.disks()).rev().fold(0, |rank, radius| {
+            rank * self.pegs as u128 + self.tower_of(radius).0 as u128
+        })
+    }
+
+    /// Returns the state with the specified rank, if it is less than k^n.
+    pub fn unrank(pegs: usize, disks: usize, rank: u128) -> Result<PackedState, PackStateError> {
+        let mut state = PackedState::new(pegs, disks, TowerSelector::A)?;
+        let width = state.width();
+        let mut rest = rank;
+        for index in 0..disks as u32 {
+            state.fields |= (rest % pegs as u128) << (index * width);
+            rest /= pegs as u128;
+        }
+        if rest != 0 {
+            return Err(PackStateError::RankOutOfRange { pegs, disks, rank });
+        }
+        Ok(state)
+    }
+}
+
+impl TryFrom<&TowerSet> for PackedState {
+    type Error = PackStateError;
+
+    /// Packs the towers.  Panics unless the disks have radii 1 through n.
+    fn try_from(towers: &TowerSet) -> Result<Self, Self::Error> {
+        PackedState::from_positions(towers.towers.len(), &towers.positions())
+    }
+}
+
+impl From<PackedState> for TowerSet {
+    fn from(state: PackedState) -> Self {
+        let mut towers = TowerSet::with_pegs(state.pegs(), 0);
+        for radius in (1..=state.disks()).rev() {
+            towers[state.tower_of(radius)].push(Disk { radius, colour: 0 });
+        }
+        towers
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+
+    const A: TowerSelector = TowerSelector::A;
+    const B: TowerSelector = TowerSelector::B;
+    const C: TowerSelector = TowerSelector::C;
+
+    /// Returns every state with the specified pegs and disks.
+    fn all_states(pegs: usize, disks: usize) -> impl Iterator<Item = PackedState> {
+        let count = (pegs as u128).pow(disks as u32);
+        (0..count).map(move |rank| PackedState::unrank(pegs, disks, rank).unwrap())
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for (pegs, disks) in [(3, 0), (3, 4), (4, 3), (5, 3), (26, 2)] {
+            let mut seen = HashSet::new();
+            for state in all_states(pegs, disks) {
+                let towers = TowerSet::from(state);
+                assert_eq!(TowerSet::from_positions(pegs, &state.positions()), towers);
+                assert_eq!(PackedState::try_from(&towers), Ok(state));
+                assert_eq!(PackedState::unrank(pegs, disks, state.rank()), Ok(state));
+                assert!(seen.insert(state), "{state:?} seen twice");
+            }
+        }
+    }
+
+    #[test]
+    fn test_capacity() {
+        assert_eq!(PackedState::capacity(3), 64);
+        assert_eq!(PackedState::capacity(4), 64);
+        assert_eq!(PackedState::capacity(5), 42);
+        assert_eq!(PackedState::capacity(26), 25);
+        let state = PackedState::new(3, 64, C).unwrap();
+        assert_eq!(state.top(C), Some(1));
+        assert_eq!(state.tower_of(64), C);
+        assert_eq!(
+            PackedState::new(3, 65, A).unwrap_err().to_string(),
+            "65 disks will not pack for 3 pegs; the most is 64"
+        );
+        assert_eq!(
+            PackedState::from_positions(27, &[A]),
+            Err(PackStateError::PegCount(27))
+        );
+        assert_eq!(
+            PackedState::new(0, 1, A).unwrap_err().to_string(),
+            "peg count should be from 1 to 26: 0"
+        );
+        assert_eq!(
+            PackedState::new(3, 2, TowerSelector(3))
+                .unwrap_err()
+                .to_string(),
+            "there is no tower D among 3 pegs"
+        );
+        assert_eq!(
+            PackedState::from_positions(3, &[A, TowerSelector(3)]),
+            Err(PackStateError::NoTower {
+                pegs: 3,
+                selector: TowerSelector(3),
+            })
+        );
+    }
+
+    #[test]
+    fn test_unrank_out_of_range() {
+        assert_eq!(
+            PackedState::unrank(3, 2, 9).unwrap_err().to_string(),
+            "rank 9 is out of range for 2 disks on 3 pegs"
+        );
+        assert!(PackedState::unrank(3, 2, 8).is_ok());
+        assert_eq!(
+            PackedState::unrank(3, 0, 1),
+            Err(PackStateError::RankOutOfRange {
+                pegs: 3,
+                disks: 0,
+                rank: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_moves_match_tower_set() {
+        for (pegs, disks) in [(3, 4), (4, 3), (6, 2)] {
+            for state in all_states(pegs, disks) {
+                let towers = TowerSet::from(state);
+                for source in (0..pegs + 1).map(TowerSelector) {
+                    for target in (0..pegs + 1).map(TowerSelector) {
+                        let mut packed = state;
+                        let mut unpacked = towers.clone();
+                        assert_eq!(
+                            packed.move_disk(source, target),
+                            unpacked.move_disk(source, target)
+                        );
+                        assert_eq!(TowerSet::from(packed), unpacked);
+                    }
+                }
+                let successors: Vec<_> = state.successors().collect();
+                for ((source, target), next) in &successors {
+                    assert_eq!(state.check_move(*source, *target).map(|_| ()), Ok(()));
+                    assert_ne!(*next, state);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_order() {
+        let state =
+            |positions: &[TowerSelector]| PackedState::from_positions(3, positions).unwrap();
+        // The largest disk matters most.
+        assert!(state(&[C, A]) < state(&[A, B]));
+        assert!(state(&[A, B]) < state(&[B, B]));
+        assert!(state(&[A, A, A]) > state(&[C, C]));
+        assert_eq!(state(&[B, C]).rank(), 1 + 2 * 3);
+    }
+}
@@ -0,0 +1,140 @@
+//! Random positions of the three-peg puzzle, for practice.  Every way of
+//! putting each disk on some tower gives exactly one legal position, since
+//! the disks on a tower can stand in only one order, so choosing the tower of
+//! each disk uniformly and independently chooses a legal position uniformly.
+//!
+//! The numbers come from SplitMix64, a small generator that is fast and good
+//! enough for games, though not for cryptography.  The same seed always gives
+//! the same positions, so a drill can be repeated by reusing its seed.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{distance_to_tower, TowerSelector, TowerSet};
+
+/// A seeded generator of pseudorandom numbers.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Returns a generator that always gives the same numbers for the same
+    /// seed.
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    /// Returns a seed that differs from run to run.
+    pub fn seed_from_time() -> u64 {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH);
+        now.map_or(0, |since| since.as_nanos() as u64)
+    }
+
+    /// Returns the next number, any of which is equally likely.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number from 0 up to, but not including, the bound, with
+    /// every number equally likely.  Panics if the bound is 0.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "no number is below 0");
+        // Numbers from the last, partial run of `bound` values would make
+        // the smallest remainders more likely, so draw again.
+        let limit = u64::MAX - u64::MAX % bound;
+        loop {
+            let next = self.next_u64();
+            if next < limit {
+                return next % bound;
+            }
+        }
+    }
+}
+
+/// A legal position, and the fewest moves that gather every disk on the
+/// target tower from it.
+pub struct Position {
+    /// The towers, three of them.
+    pub towers: TowerSet,
+    /// The fewest moves that gather every disk on the target tower.
+    pub distance: u64,
+}
+
+impl Position {
+    /// Returns a position of three towers chosen uniformly at random.  Panics
+    /// if there are more than 64 disks, since the distance might not fit.
+    pub fn random(rng: &mut Rng, disks: usize, target: TowerSelector) -> Position {
+        assert!(disks <= 64, "random positions support at most 64 disks");
+        let positions: Vec<_> = (0..disks)
+            .map(|_| TowerSelector(rng.below(3) as usize))
+            .collect();
+        Position {
+            towers: TowerSet::from_positions(3, &positions),
+            distance: distance_to_tower(&positions, target)
+                .expect("the distance for at most 64 disks should fit in a u64"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hanoi::solve_from;
+    use std::collections::HashMap;
+
+    const B: TowerSelector = TowerSelector::B;
+
+    #[test]
+    fn test_reproducible() {
+        let draws = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..8).map(|_| rng.next_u64()).collect::<Vec<_>>()
+        };
+        assert_eq!(draws(42), draws(42));
+        assert_ne!(draws(42), draws(43));
+        // The first output of SplitMix64 seeded with 0.
+        assert_eq!(Rng::new(0).next_u64(), 0xe220_a839_7b1d_cdaf);
+    }
+
+    #[test]
+    fn test_below() {
+        let mut rng = Rng::new(7);
+        for bound in [1, 2, 3, 10, u64::MAX] {
+            for _ in 0..100 {
+                assert!(rng.below(bound) < bound);
+            }
+        }
+    }
+
+    #[test]
+    fn test_uniform_positions() {
+        // Each of the 27 positions of 3 disks should come up about 1000
+        // times in 27000 draws.
+        let mut rng = Rng::new(1);
+        let mut counts = HashMap::new();
+        for _ in 0..27000 {
+            let towers = Position::random(&mut rng, 3, B).towers;
+            *counts.entry(towers).or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 27);
+        for count in counts.values() {
+            assert!((850..1150).contains(count), "{count}");
+        }
+    }
+
+    #[test]
+    fn test_distance() {
+        let mut rng = Rng::new(2);
+        for disks in 0..=8 {
+            for _ in 0..20 {
+                let position = Position::random(&mut rng, disks, B);
+                let moves = solve_from(&position.towers, B);
+                assert_eq!(position.distance, moves.len() as u64);
+                assert!(position.distance < 1 << disks);
+            }
+        }
+    }
+}
@@ -0,0 +1,324 @@
+//! Ways of drawing a `TowerSet`.  Each renderer writes one frame at a time,
+//! given the towers and the move that led to them, so the same solver can
+//! draw ASCII art for a person, a single line for a log, or JSON for another
+//! program.
+
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use super::{Disk, Move, TowerSet, TowerSetDisplay};
+
+/// Something that can draw the towers.
+pub trait Render {
+    /// Writes a frame showing the towers, given the move that led to them,
+    /// if any.  The frame ends with a newline.
+    fn render(&self, out: &mut dyn Write, towers: &TowerSet, last: Option<Move>) -> io::Result<()>;
+
+    /// Whether frames take up several lines, and so need a blank line
+    /// between them when printed one below another.
+    fn is_multiline(&self) -> bool {
+        true
+    }
+}
+
+/// The original ASCII art, with each disk labelled by its radius, narrowed
+/// if necessary to fit the specified number of columns.
+pub struct Ascii {
+    /// The number of columns to fit the towers in, if known.
+    pub width: Option<usize>,
+}
+
+impl Render for Ascii {
+    fn render(&self, out: &mut dyn Write, towers: &TowerSet, _: Option<Move>) -> io::Result<()> {
+        match self.width {
+            Some(width) => writeln!(out, "{}", TowerSetDisplay::new(towers).fit_width(width)),
+            None => writeln!(out, "{towers}"),
+        }
+    }
+}
+
+/// Box-drawing characters, with a different colour for each size of disk
+/// (repeating after twelve sizes), and a different shade for disks of the
+/// same size but different colours.
+pub struct Unicode;
+
+impl Unicode {
+    /// ANSI foreground colours: the six normal colours other than black and
+    /// white, then their bright versions.
+    const COLOURS: [u8; 12] = [31, 32, 33, 34, 35, 36, 91, 92, 93, 94, 95, 96];
+
+    /// Blocks filling disks of each colour.
+    const SHADES: [&'static str; 4] = ["█", "▓", "▒", "░"];
+
+    fn write_pole(out: &mut dyn Write, radius: usize) -> io::Result<()> {
+        write!(out, " {:radius$}┃{:radius$}", "", "")
+    }
+}
+
+impl Render for Unicode {
+    fn render(&self, out: &mut dyn Write, towers: &TowerSet, _: Option<Move>) -> io::Result<()> {
+        let radius = towers.disks().map(|disk| disk.radius).max().unwrap_or(0);
+        let disk_count = towers.disks().count();
+        for _ in towers {
+            Unicode::write_pole(out, radius)?;
+        }
+        writeln!(out)?;
+        for depth in (0..disk_count).rev() {
+            for tower in towers {
+                match tower.disks.get(depth) {
+                    Some(disk) => {
+                        let colour = Unicode::COLOURS[(disk.radius - 1) % Unicode::COLOURS.len()];
+                        write!(
+                            out,
+                            " {blank:gap$}\x1b[{colour}m{fill}\x1b[0m{blank:gap$}",
+                            blank = "",
+                            fill = Unicode::SHADES[disk.colour % Unicode::SHADES.len()]
+                                .repeat(2 * disk.radius + 1),
+                            gap = radius - disk.radius,
+                        )?;
+                    }
+                    None => Unicode::write_pole(out, radius)?,
+                }
+            }
+            writeln!(out)?;
+        }
+        for _ in towers {
+            write!(out, "━{0}┻{0}", "━".repeat(radius))?;
+        }
+        writeln!(out)?;
+        for label in towers.selectors() {
+            write!(out, " {:radius$}{label}{:radius$}", "", "")?;
+        }
+        writeln!(out)
+    }
+}
+
+/// A single line listing the radii of the disks on each tower, from bottom
+/// to top, such as `A[3 2] B[1] C[]`.  Disks of any colour but the first
+/// have the colour's letter after the radius, as in `A[3 3b]`.
+///
+/// ```
+/// use part3::hanoi::render::{Compact, Render};
+/// use part3::hanoi::TowerSet;
+///
+/// let mut out = Vec::new();
+/// Compact.render(&mut out, &TowerSet::with_pegs(3, 2), None).unwrap();
+/// assert_eq!(out, b"A[2 1] B[] C[]\n");
+/// ```
+pub struct Compact;
+
+impl Render for Compact {
+    fn render(&self, out: &mut dyn Write, towers: &TowerSet, _: Option<Move>) -> io::Result<()> {
+        for (index, (label, tower)) in towers.selectors().zip(towers).enumerate() {
+            let radii: Vec<_> = tower.disks.iter().map(Disk::to_string).collect();
+            let sep = if index == 0 { "" } else { " " };
+            write!(out, "{sep}{label}[{}]", radii.join(" "))?;
+        }
+        writeln!(out)
+    }
+
+    fn is_multiline(&self) -> bool {
+        false
+    }
+}
+
+/// One JSON object per line, giving the last move (or null) and the radii of
+/// the disks on each tower, from bottom to top.  Disks of any colour but the
+/// first are objects giving the radius and the number of the colour:
+///
+/// ```text
+/// {"move":"AB","towers":{"A":[3,2],"B":[1,{"radius":1,"colour":1}],"C":[]}}
+/// ```
+pub struct Json;
+
+impl Render for Json {
+    fn render(&self, out: &mut dyn Write, towers: &TowerSet, last: Option<Move>) -> io::Result<()> {
+        match last {
+            Some((source, target)) => write!(out, "{{\"move\":\"{source}{target}\",")?,
+            None => write!(out, "{{\"move\":null,")?,
+        }
+        write!(out, "\"towers\":{{")?;
+        for (index, (label, tower)) in towers.selectors().zip(towers).enumerate() {
+            let radii: Vec<_> = tower
+                .disks
+                .iter()
+                .map(|disk| match disk.colour {
+                    0 => disk.radius.to_string(),
+                    colour => format!("{{\"radius\":{},\"colour\":{colour}}}", disk.radius),
+                })
+                .collect();
+            let sep = if index == 0 { "" } else { "," };
+            write!(out, "{sep}\"{label}\":[{}]", radii.join(","))?;
+        }
+        writeln!(out, "}}}}")
+    }
+
+    fn is_multiline(&self) -> bool {
+        false
+    }
+}
+
+/// Draws each frame in place of the last, using ANSI escape codes to clear
+/// the terminal first.  Of course, this works only in terminals that
+/// understand these sequences.  See also:
+/// https://stackoverflow.com/a/37778152/3116635
+pub struct Animated<'a>(pub &'a dyn Render);
+
+impl Render for Animated<'_> {
+    fn render(&self, out: &mut dyn Write, towers: &TowerSet, last: Option<Move>) -> io::Result<()> {
+        write!(out, "\x1b[2J\x1b[H")?;
+        self.0.render(out, towers, last)
+    }
+
+    fn is_multiline(&self) -> bool {
+        self.0.is_multiline()
+    }
+}
+
+/// The renderers that can be chosen from the command line.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Style {
+    /// See `Ascii`.
+    Ascii,
+    /// See `Unicode`.
+    Unicode,
+    /// See `Compact`.
+    Compact,
+    /// See `Json`.
+    Json,
+}
+
+impl Style {
+    /// Returns a renderer in this style, fitting the specified number of
+    /// columns if possible.
+    pub fn renderer(self, width: Option<usize>) -> Box<dyn Render> {
+        match self {
+            Style::Ascii => Box::new(Ascii { width }),
+            Style::Unicode => Box::new(Unicode),
+            Style::Compact => Box::new(Compact),
+            Style::Json => Box::new(Json),
+        }
+    }
+}
+
+impl FromStr for Style {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ascii" => Ok(Style::Ascii),
+            "unicode" => Ok(Style::Unicode),
+            "compact" => Ok(Style::Compact),
+            "json" => Ok(Style::Json),
+            _ => Err(format!("{s}: bad style")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hanoi::TowerSelector;
+
+    const A: TowerSelector = TowerSelector::A;
+    const B: TowerSelector = TowerSelector::B;
+    const C: TowerSelector = TowerSelector::C;
+
+    fn render(renderer: &dyn Render, towers: &TowerSet, last: Option<Move>) -> String {
+        let mut out = Vec::new();
+        renderer.render(&mut out, towers, last).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_ascii() {
+        let towers = TowerSet::from_positions(3, &[B, A]);
+        assert_eq!(
+            render(&Ascii { width: None }, &towers, None),
+            format!("{towers}\n")
+        );
+        let want = [
+            "  ||   ||   || ",
+            "  ||   ||   || ",
+            " @_2@ @_1@  || ",
+            "   A    B    C ",
+        ];
+        assert_eq!(
+            render(&Ascii { width: Some(16) }, &towers, None),
+            want.join("\n") + "\n"
+        );
+    }
+
+    #[test]
+    fn test_unicode() {
+        let towers = TowerSet::from_positions(3, &[B, A]);
+        let want = [
+            "   ┃     ┃     ┃  ",
+            "   ┃     ┃     ┃  ",
+            " \x1b[32m█████\x1b[0m  \x1b[31m███\x1b[0m    ┃  ",
+            "━━━┻━━━━━┻━━━━━┻━━",
+            "   A     B     C  ",
+        ];
+        assert_eq!(render(&Unicode, &towers, None), want.join("\n") + "\n");
+    }
+
+    #[test]
+    fn test_compact() {
+        let towers = TowerSet::from_positions(3, &[B, A, A]);
+        assert_eq!(render(&Compact, &towers, None), "A[3 2] B[1] C[]\n");
+        assert!(!Compact.is_multiline());
+    }
+
+    #[test]
+    fn test_colours() {
+        let mut towers = TowerSet::with_pegs(3, 0);
+        towers[A].disks = vec![
+            Disk {
+                radius: 1,
+                colour: 0,
+            },
+            Disk {
+                radius: 1,
+                colour: 1,
+            },
+        ];
+        assert_eq!(render(&Compact, &towers, None), "A[1 1b] B[] C[]\n");
+        assert_eq!(
+            render(&Json, &towers, None),
+            "{\"move\":null,\"towers\":{\"A\":[1,{\"radius\":1,\"colour\":1}],\"B\":[],\"C\":[]}}\n"
+        );
+        assert!(render(&Ascii { width: None }, &towers, None).contains("#_1#"));
+        assert!(render(&Unicode, &towers, None).contains("\x1b[31m▓▓▓\x1b[0m"));
+    }
+
+    #[test]
+    fn test_json() {
+        let towers = TowerSet::from_positions(3, &[B, A, A]);
+        assert_eq!(
+            render(&Json, &towers, Some((A, B))),
+            "{\"move\":\"AB\",\"towers\":{\"A\":[3,2],\"B\":[1],\"C\":[]}}\n"
+        );
+        assert_eq!(
+            render(&Json, &TowerSet::with_pegs(3, 0), None),
+            "{\"move\":null,\"towers\":{\"A\":[],\"B\":[],\"C\":[]}}\n"
+        );
+    }
+
+    #[test]
+    fn test_animated() {
+        let towers = TowerSet::from_positions(3, &[C]);
+        assert_eq!(
+            render(&Animated(&Compact), &towers, None),
+            "\x1b[2J\x1b[HA[] B[] C[1]\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_style() {
+        assert_eq!("unicode".parse(), Ok(Style::Unicode));
+        assert_eq!(
+            "fancy".parse::<Style>(),
+            Err("fancy: bad style".to_string())
+        );
+    }
+}
@@ -0,0 +1,622 @@
+//! Variants of the puzzle that forbid some moves between towers, on top of the
+//! usual rule that no disk may go on a smaller one.  Each variant has its own
+//! recursive solution, and its own count of the fewest moves:
+//!
+//! - In the classic puzzle, any tower may move to any other, and a tower of
+//!   n disks takes 2^n - 1 moves.
+//! - In the cyclic puzzle, disks may only move one tower along the cycle
+//!   A → B → C → A.  Moving a tower one step along the cycle takes 1, 5, 15,
+//!   43, 119, ... moves, and two steps 2, 7, 21, 59, 163, ... moves.
+//! - In the adjacent puzzle, the towers stand in a row, and disks may only
+//!   move between neighbours, never directly between A and C.  Moving a tower
+//!   from one end to the other takes 3^n - 1 moves, passing through every
+//!   legal state.
+//! - In the digraph puzzle, disks may only move along the listed arcs, such as
+//!   `AB,BC,CA,BA`.
+//! - In the magnetic puzzle, disks may move between any two towers, but each
+//!   has a red face and a blue face, and turns over as it moves.  Like faces
+//!   repel, so a disk may only rest on one with the same face up.  Solutions
+//!   are found by searching every state.
+//!
+//! Every variant is for three towers.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::variant::{self, Goal};
+use super::{moves, third, Disk, Move, TowerSelector, TowerSelectorSet, TowerSet};
+
+/// The number of towers in every variant.
+pub const PEGS: usize = 3;
+
+/// The moves between towers that a variant of the puzzle allows.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Rules {
+    /// Disks may move between any two towers.
+    Classic,
+    /// Disks may only move one tower along the cycle A → B → C → A.
+    Cyclic,
+    /// Disks may only move between neighbouring towers, A ↔ B ↔ C.
+    Adjacent,
+    /// Disks may only move along these arcs, each from a source to a target.
+    Digraph(Vec<Move>),
+    /// Disks turn over as they move, and may only rest on disks with the same
+    /// colour face up.
+    Magnetic,
+}
+
+impl Rules {
+    /// Returns whether a disk may move from the source tower to the target
+    /// tower, if it would not go on a smaller disk.
+    pub fn allows(&self, source: TowerSelector, target: TowerSelector) -> bool {
+        match self {
+            Rules::Classic | Rules::Magnetic => true,
+            Rules::Cyclic => target == next(source),
+            Rules::Adjacent => source.0.abs_diff(target.0) == 1,
+            Rules::Digraph(arcs) => arcs.contains(&(source, target)),
+        }
+    }
+
+    /// Returns the disk as it lands after moving.
+    pub fn landed(&self, disk: &Disk) -> Disk {
+        match self {
+            Rules::Magnetic => Disk {
+                colour: 1 - disk.colour % 2,
+                ..disk.clone()
+            },
+            _ => disk.clone(),
+        }
+    }
+
+    /// Returns whether a disk, as it lands, may rest on the disk below,
+    /// which is no smaller.
+    pub fn may_rest_on(&self, disk: &Disk, below: &Disk) -> bool {
+        match self {
+            Rules::Magnetic => disk.colour == below.colour,
+            _ => true,
+        }
+    }
+
+    /// Returns whether solutions are found by searching every state.
+    pub fn is_searched(&self) -> bool {
+        *self == Rules::Magnetic
+    }
+
+    /// Returns every move between two of the three towers that the rules
+    /// allow.
+    pub fn arcs(&self) -> Vec<Move> {
+        let selectors = (0..PEGS).map(TowerSelector);
+        selectors
+            .clone()
+            .flat_map(|source| selectors.clone().map(move |target| (source, target)))
+            .filter(|&(source, target)| source != target && self.allows(source, target))
+            .collect()
+    }
+
+    /// Returns the fewest moves that take a tower of the specified height
+    /// from the source tower to the target tower, or None if the rules
+    /// never let it get there.  Except in the magnetic puzzle, whose moves
+    /// are found by searching, the moves are made lazily, like those of
+    /// `moves`.
+    pub fn moves(
+        &self,
+        height: usize,
+        source: TowerSelector,
+        target: TowerSelector,
+    ) -> Option<Box<dyn Iterator<Item = Move>>> {
+        let solution = match self {
+            Rules::Classic if source == target => return Some(Box::new(std::iter::empty())),
+            Rules::Classic => {
+                let buffer = third(source, target);
+                let selectors = TowerSelectorSet::from_parts(source, target, buffer);
+                return Some(Box::new(moves(height, selectors)));
+            }
+            Rules::Magnetic => {
+                let start = TowerSet::from_positions(PEGS, &vec![source; height]);
+                let found = variant::search(&start, &Goal::Gather(target), self)?;
+                return Some(Box::new(found.into_iter()));
+            }
+            Rules::Cyclic => Solution::Cyclic,
+            Rules::Adjacent => Solution::Adjacent,
+            Rules::Digraph(arcs) => {
+                let solver = DigraphSolver::new(arcs, height);
+                if !solver.reachable(height, source, target) {
+                    return None;
+                }
+                Solution::Digraph(solver)
+            }
+        };
+        Some(Box::new(RulesMoves {
+            solution,
+            stack: vec![Pending::Call(height, source, target)],
+        }))
+    }
+}
+
+impl fmt::Display for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rules::Classic => write!(f, "classic"),
+            Rules::Cyclic => write!(f, "cyclic"),
+            Rules::Adjacent => write!(f, "adjacent"),
+            Rules::Magnetic => write!(f, "magnetic"),
+            Rules::Digraph(arcs) => {
+                let arcs: Vec<_> = arcs
+                    .iter()
+                    .map(|(source, target)| format!("{source}{target}"))
+                    .collect();
+                write!(f, "{}", arcs.join(","))
+            }
+        }
+    }
+}
+
+impl FromStr for Rules {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "classic" => Ok(Rules::Classic),
+            "cyclic" => Ok(Rules::Cyclic),
+            "adjacent" => Ok(Rules::Adjacent),
+            "magnetic" => Ok(Rules::Magnetic),
+            _ => {
+                let arc = |arc: &str| match arc.as_bytes() {
+                    [source @ b'A'..=b'C', target @ b'A'..=b'C'] if source != target => Some((
+                        TowerSelector(usize::from(source - b'A')),
+                        TowerSelector(usize::from(target - b'A')),
+                    )),
+                    _ => None,
+                };
+                let arcs: Option<Vec<_>> = s.split(',').map(arc).collect();
+                arcs.map(Rules::Digraph)
+                    .ok_or_else(|| format!("{s}: bad rules"))
+            }
+        }
+    }
+}
+
+/// Returns the tower after the specified one along the cycle A → B → C → A.
+fn next(selector: TowerSelector) -> TowerSelector {
+    TowerSelector((selector.0 + 1) % PEGS)
+}
+
+/// Work remaining for the `RulesMoves` iterator: either a recursive call not
+/// yet begun, to move a tower of some height from a source to a target, or a
+/// move waiting for the calls before it to finish.
+enum Pending {
+    Call(usize, TowerSelector, TowerSelector),
+    Move(Move),
+}
+
+/// The recursive solution that a `RulesMoves` iterator follows.
+enum Solution {
+    Cyclic,
+    Adjacent,
+    Digraph(DigraphSolver),
+}
+
+/// Iterator over the moves of a variant's recursive solution.  Like the
+/// iterator returned by `moves`, it keeps a stack of the work remaining in
+/// each call along the path to the current move, rather than making the
+/// recursive calls up front.
+struct RulesMoves {
+    solution: Solution,
+    stack: Vec<Pending>,
+}
+
+impl Iterator for RulesMoves {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        loop {
+            match self.stack.pop()? {
+                Pending::Move(next) => return Some(next),
+                Pending::Call(height, source, target) => {
+                    // The work of the call is pushed in order, and then
+                    // reversed, so that its first step is popped first.
+                    let start = self.stack.len();
+                    let steps = &mut self.stack;
+                    match &self.solution {
+                        Solution::Cyclic => push_cyclic_steps(height, source, target, steps),
+                        Solution::Adjacent => push_adjacent_steps(height, source, target, steps),
+                        Solution::Digraph(solver) => {
+                            solver.push_steps(height, source, target, steps)
+                        }
+                    }
+                    self.stack[start..].reverse();
+                }
+            }
+        }
+    }
+}
+
+/// Appends the steps that take a tower of the specified height from the
+/// source tower to the target tower in the fewest moves, moving only along
+/// the cycle.
+fn push_cyclic_steps(
+    height: usize,
+    source: TowerSelector,
+    target: TowerSelector,
+    steps: &mut Vec<Pending>,
+) {
+    if height == 0 || source == target {
+        return; // BASE CASE: Nothing to move.
+    }
+    if target == next(source) {
+        // RECURSIVE CASE: One step.  The smaller disks must get out of the
+        // way onto the third tower, which is two steps along from the source,
+        // and then come back two more steps to land on the target.
+        let other = next(target);
+        steps.push(Pending::Call(height - 1, source, other));
+        steps.push(Pending::Move((source, target)));
+        steps.push(Pending::Call(height - 1, other, target));
+    } else {
+        // RECURSIVE CASE: Two steps.  The largest disk stops on the tower
+        // between, and the smaller disks make way for it each time.
+        let between = next(source);
+        steps.push(Pending::Call(height - 1, source, target));
+        steps.push(Pending::Move((source, between)));
+        steps.push(Pending::Call(height - 1, target, source));
+        steps.push(Pending::Move((between, target)));
+        steps.push(Pending::Call(height - 1, source, target));
+    }
+}
+
+/// Appends the steps that take a tower of the specified height from the
+/// source tower to the target tower in the fewest moves, moving only between
+/// neighbours.
+fn push_adjacent_steps(
+    height: usize,
+    source: TowerSelector,
+    target: TowerSelector,
+    steps: &mut Vec<Pending>,
+) {
+    if height == 0 || source == target {
+        return; // BASE CASE: Nothing to move.
+    }
+    let other = third(source, target);
+    if source.0.abs_diff(target.0) == 1 {
+        // RECURSIVE CASE: Neighbours.  The smaller disks wait on the third
+        // tower while the largest disk moves directly.
+        steps.push(Pending::Call(height - 1, source, other));
+        steps.push(Pending::Move((source, target)));
+        steps.push(Pending::Call(height - 1, other, target));
+    } else {
+        // RECURSIVE CASE: Opposite ends.  The largest disk stops in the
+        // middle, and the smaller disks cross over and back to make way.
+        steps.push(Pending::Call(height - 1, source, target));
+        steps.push(Pending::Move((source, other)));
+        steps.push(Pending::Call(height - 1, target, source));
+        steps.push(Pending::Move((other, target)));
+        steps.push(Pending::Call(height - 1, source, target));
+    }
+}
+
+/// Fewest-move solutions for any set of allowed moves between three towers.
+/// Whenever the largest disk moves, every smaller disk must be stacked on the
+/// third tower, so a solution is a walk of the largest disk along the arcs,
+/// with the smaller disks moving as a complete tower from one resting place
+/// to the next between its steps.  The cheapest walk is found by searching
+/// over the arcs, given the costs for one disk fewer.
+pub struct DigraphSolver {
+    arcs: Vec<Move>,
+    /// The fewest moves for each height, source, and target, or None if the
+    /// target cannot be reached.  Counts too big for a u64 saturate at
+    /// u64::MAX.
+    counts: Vec<[[Option<u64>; PEGS]; PEGS]>,
+    /// The walk of the largest disk for each height, source, and target.
+    walks: Vec<[[Vec<Move>; PEGS]; PEGS]>,
+}
+
+impl DigraphSolver {
+    /// Tabulates solutions for up to the specified number of disks.
+    pub fn new(arcs: &[Move], height: usize) -> DigraphSolver {
+        let mut solver = DigraphSolver {
+            arcs: arcs.to_vec(),
+            counts: vec![[[Some(0); PEGS]; PEGS]],
+            walks: vec![Default::default()],
+        };
+        for n in 1..=height {
+            let mut counts = [[None; PEGS]; PEGS];
+            let mut walks: [[Vec<Move>; PEGS]; PEGS] = Default::default();
+            for source in (0..PEGS).map(TowerSelector) {
+                for target in (0..PEGS).map(TowerSelector) {
+                    let (count, walk) = solver.cheapest_walk(n, source, target);
+                    counts[source.0][target.0] = count;
+                    walks[source.0][target.0] = walk;
+                }
+            }
+            solver.counts.push(counts);
+            solver.walks.push(walks);
+        }
+        solver
+    }
+
+    /// Returns whether the rules let a tower of the specified height get
+    /// from the source tower to the target tower.
+    pub fn reachable(&self, height: usize, source: TowerSelector, target: TowerSelector) -> bool {
+        self.counts[height][source.0][target.0].is_some()
+    }
+
+    /// Returns the fewest moves that take a tower of the specified height
+    /// from the source tower to the target tower, or None if it cannot get
+    /// there, or if the count does not fit in a u64.
+    pub fn count(
+        &self,
+        height: usize,
+        source: TowerSelector,
+        target: TowerSelector,
+    ) -> Option<u64> {
+        self.counts[height][source.0][target.0].filter(|&count| count != u64::MAX)
+    }
+
+    /// Returns the cost and the walk of the largest of `height` disks, using
+    /// the costs for `height - 1` disks, which must already be tabulated, or
+    /// None and no walk if the target cannot be reached.
+    fn cheapest_walk(
+        &self,
+        height: usize,
+        source: TowerSelector,
+        target: TowerSelector,
+    ) -> (Option<u64>, Vec<Move>) {
+        if source == target {
+            return (Some(0), Vec::new());
+        }
+        let smaller = &self.counts[height - 1];
+        let rest = |from: TowerSelector, to: TowerSelector| smaller[from.0][to.0];
+        // The cost of each walk ending with each arc, including the moves of
+        // the smaller disks onto the third tower before it, found by relaxing
+        // every pair of consecutive arcs until nothing changes.  Costs never
+        // go negative, so no walk worth taking has more steps than there are
+        // arcs.
+        let mut costs: Vec<Option<u64>> = self
+            .arcs
+            .iter()
+            .map(|&(from, to)| match from == source {
+                true => rest(source, third(from, to)).map(|cost| cost.saturating_add(1)),
+                false => None,
+            })
+            .collect();
+        let mut previous = vec![None; self.arcs.len()];
+        for _ in 0..self.arcs.len() {
+            for (before, &(u, v)) in self.arcs.iter().enumerate() {
+                for (after, &(from, to)) in self.arcs.iter().enumerate() {
+                    let Some(walked) = costs[before].filter(|_| from == v) else {
+                        continue;
+                    };
+                    let Some(cost) = rest(third(u, v), third(from, to))
+                        .map(|rest| walked.saturating_add(rest).saturating_add(1))
+                    else {
+                        continue;
+                    };
+                    if costs[after].is_none_or(|known| cost < known) {
+                        costs[after] = Some(cost);
+                        previous[after] = Some(before);
+                    }
+                }
+            }
+        }
+        // Finally, the smaller disks gather on the target.
+        let best = (0..self.arcs.len())
+            .filter(|&last| self.arcs[last].1 == target)
+            .filter_map(|last| {
+                let (from, to) = self.arcs[last];
+                let cost = costs[last]?.saturating_add(rest(third(from, to), target)?);
+                Some((cost, last))
+            })
+            .min();
+        let Some((cost, last)) = best else {
+            return (None, Vec::new());
+        };
+        let mut walk = vec![self.arcs[last]];
+        let mut step = last;
+        while let Some(before) = previous[step] {
+            walk.push(self.arcs[before]);
+            step = before;
+        }
+        walk.reverse();
+        (Some(cost), walk)
+    }
+
+    /// Appends the steps that take a tower of the specified height from the
+    /// source tower to the target tower, which must be reachable, in the
+    /// fewest moves.
+    fn push_steps(
+        &self,
+        height: usize,
+        source: TowerSelector,
+        target: TowerSelector,
+        steps: &mut Vec<Pending>,
+    ) {
+        if height == 0 {
+            return; // BASE CASE: No disks to move.
+        }
+        // RECURSIVE CASE: The smaller disks make way for each step of the
+        // largest disk's walk, and then gather on top of it.
+        let mut rest = source;
+        for &(from, to) in &self.walks[height][source.0][target.0] {
+            let aside = third(from, to);
+            steps.push(Pending::Call(height - 1, rest, aside));
+            steps.push(Pending::Move((from, to)));
+            rest = aside;
+        }
+        steps.push(Pending::Call(height - 1, rest, target));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hanoi::packed::PackedState;
+    use crate::hanoi::IllegalMoveError;
+    use std::collections::{HashMap, VecDeque};
+
+    const A: TowerSelector = TowerSelector::A;
+    const B: TowerSelector = TowerSelector::B;
+    const C: TowerSelector = TowerSelector::C;
+
+    /// Returns the fewest moves between two towers under the rules, found by
+    /// breadth-first search over every state.
+    fn search(
+        rules: &Rules,
+        height: usize,
+        source: TowerSelector,
+        target: TowerSelector,
+    ) -> Option<u64> {
+        let start = PackedState::new(PEGS, height, source).unwrap();
+        let goal = PackedState::new(PEGS, height, target).unwrap();
+        let mut distances = HashMap::from([(start, 0)]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(state) = queue.pop_front() {
+            for ((from, to), next) in state.successors() {
+                if rules.allows(from, to) && !distances.contains_key(&next) {
+                    distances.insert(next, distances[&state] + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        distances.get(&goal).copied()
+    }
+
+    /// Checks that the moves are legal under the rules, and take the tower
+    /// from the source to the target, and returns how many there are.
+    fn check(rules: &Rules, height: usize, source: TowerSelector, target: TowerSelector) -> u64 {
+        let mut towers = TowerSet::from_positions(PEGS, &vec![source; height]);
+        let mut count = 0;
+        for (from, to) in rules.moves(height, source, target).unwrap() {
+            towers.move_disk_with(rules, from, to).unwrap();
+            count += 1;
+        }
+        assert_eq!(towers[target].disks.len(), height);
+        count
+    }
+
+    #[test]
+    fn test_classic() {
+        for height in 0..=6 {
+            assert_eq!(check(&Rules::Classic, height, A, C), (1 << height) - 1);
+        }
+        assert_eq!(Rules::Classic.arcs().len(), 6);
+    }
+
+    #[test]
+    fn test_cyclic() {
+        let one_step = [0, 1, 5, 15, 43, 119, 327];
+        let two_steps = [0, 2, 7, 21, 59, 163, 447];
+        for height in 0..=6 {
+            assert_eq!(check(&Rules::Cyclic, height, A, B), one_step[height]);
+            assert_eq!(check(&Rules::Cyclic, height, C, A), one_step[height]);
+            assert_eq!(check(&Rules::Cyclic, height, A, C), two_steps[height]);
+            assert_eq!(check(&Rules::Cyclic, height, B, A), two_steps[height]);
+        }
+        assert_eq!(Rules::Cyclic.arcs(), [(A, B), (B, C), (C, A)]);
+    }
+
+    #[test]
+    fn test_adjacent() {
+        for height in 0..=6 {
+            let all = 3u64.pow(height as u32) - 1;
+            assert_eq!(check(&Rules::Adjacent, height, A, C), all);
+            assert_eq!(check(&Rules::Adjacent, height, C, A), all);
+            assert_eq!(check(&Rules::Adjacent, height, A, B), all / 2);
+            assert_eq!(check(&Rules::Adjacent, height, B, C), all / 2);
+        }
+        assert_eq!(Rules::Adjacent.arcs(), [(A, B), (B, A), (B, C), (C, B)]);
+    }
+
+    #[test]
+    fn test_digraph() {
+        let digraphs = [
+            "AB,BC,CA",
+            "AB,BA,BC,CB",
+            "AB,BC,CA,BA",
+            "AC,CB,BA,AB",
+            "AB,BA,AC,CA,BC,CB",
+        ];
+        for digraph in digraphs {
+            let rules: Rules = digraph.parse().unwrap();
+            let solver = DigraphSolver::new(&rules.arcs(), 5);
+            for height in 0..=5 {
+                for source in [A, B, C] {
+                    for target in [A, B, C] {
+                        let want = search(&rules, height, source, target);
+                        assert_eq!(solver.count(height, source, target), want, "{digraph}");
+                        assert_eq!(
+                            Some(check(&rules, height, source, target)),
+                            want,
+                            "{digraph}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_too_many_to_count() {
+        // Moving 45 disks one step around the cycle takes more than 2^64
+        // moves, but they can still be made, one at a time.
+        let rules: Rules = "AB,BC,CA".parse().unwrap();
+        let solver = DigraphSolver::new(&rules.arcs(), 45);
+        assert!(solver.reachable(45, A, B));
+        assert_eq!(solver.count(45, A, B), None);
+        assert!(solver.count(40, A, B).is_some());
+        let moves = rules.moves(45, A, B).unwrap();
+        let first: Vec<_> = moves.take(8).collect();
+        let cyclic: Vec<_> = Rules::Cyclic.moves(45, A, B).unwrap().take(8).collect();
+        assert_eq!(first, cyclic);
+        let mut towers = TowerSet::from_positions(PEGS, &[A; 45]);
+        for (from, to) in Rules::Adjacent.moves(45, A, C).unwrap().take(1000) {
+            towers.move_disk_with(&Rules::Adjacent, from, to).unwrap();
+        }
+        assert_eq!(solver.count(45, A, A), Some(0));
+    }
+
+    #[test]
+    fn test_magnetic() {
+        // Every disk starts red face up, so the smallest disk must turn over
+        // twice more before it can rest on the next, blue face up.
+        let wants = [0, 1, 4];
+        for (height, want) in wants.into_iter().enumerate() {
+            assert_eq!(check(&Rules::Magnetic, height, A, B), want);
+        }
+        let mut towers = TowerSet::from_positions(PEGS, &[A, A]);
+        towers.move_disk_with(&Rules::Magnetic, A, C).unwrap();
+        towers.move_disk_with(&Rules::Magnetic, A, B).unwrap();
+        assert_eq!(
+            towers.move_disk_with(&Rules::Magnetic, C, B),
+            Err(IllegalMoveError::RepellingDisk {
+                source: C,
+                target: B,
+                radius: 1,
+                below: 2,
+            })
+        );
+        assert_eq!(towers[B].top().map(|disk| disk.colour), Some(1));
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let rules: Rules = "AB,BC".parse().unwrap();
+        let moves = |height, source, target| {
+            rules
+                .moves(height, source, target)
+                .map(Iterator::collect::<Vec<_>>)
+        };
+        assert_eq!(moves(0, C, A), Some(vec![]));
+        assert_eq!(moves(1, A, C), Some(vec![(A, B), (B, C)]));
+        assert_eq!(moves(1, C, A), None);
+        assert_eq!(moves(2, A, C), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("cyclic".parse(), Ok(Rules::Cyclic));
+        assert_eq!("AB,CA".parse(), Ok(Rules::Digraph(vec![(A, B), (C, A)])));
+        assert_eq!("AB,CA".parse::<Rules>().unwrap().to_string(), "AB,CA");
+        for bad in ["", "AA", "AB,", "AD", "ab", "linear"] {
+            assert_eq!(bad.parse::<Rules>(), Err(format!("{bad}: bad rules")));
+        }
+    }
+}
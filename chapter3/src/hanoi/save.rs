@@ -0,0 +1,334 @@
+//! A plain-text format for saving and resuming interactive games.  A saved
+//! game lists the number of pegs, the target tower, the disks on each tower
+//! from bottom to top, and the moves made so far, if any:
+//!
+//! ```text
+//! pegs 3
+//! target B
+//! A 3
+//! B 2
+//! C 1
+//! moves AC AB
+//! ```
+//!
+//! Blank lines and lines starting with `#` are ignored, so the format is easy
+//! to write by hand.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::notation::parse_move;
+use super::{Disk, Move, ParseTextError, Tower, TowerSelector, TowerSet};
+
+/// The state of an interactive game: the towers as they stand now, the tower
+/// the player is trying to reach, and the moves that got them here.
+#[derive(Debug, Eq, PartialEq)]
+pub struct SavedGame {
+    /// The towers as they stand now.
+    pub towers: TowerSet,
+    /// The tower on which every disk must be gathered to win.
+    pub target: TowerSelector,
+    /// The moves made so far, oldest first.
+    pub history: Vec<Move>,
+}
+
+impl SavedGame {
+    /// Returns the towers as they stood before any of the moves were made.
+    pub fn start(&self) -> TowerSet {
+        let mut towers = self.towers.clone();
+        for &(source, target) in self.history.iter().rev() {
+            towers
+                .move_disk(target, source)
+                .expect("saved moves should have been checked when parsed");
+        }
+        towers
+    }
+}
+
+impl fmt::Display for SavedGame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "pegs {}", self.towers.towers.len())?;
+        writeln!(f, "target {}", self.target)?;
+        for (selector, tower) in self.towers.selectors().zip(&self.towers) {
+            write!(f, "{selector}")?;
+            for disk in &tower.disks {
+                write!(f, " {}", disk.radius)?;
+            }
+            writeln!(f)?;
+        }
+        if !self.history.is_empty() {
+            write!(f, "moves")?;
+            for (source, target) in &self.history {
+                write!(f, " {source}{target}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// A whitespace-separated word of a saved game, and where it was found.
+#[derive(Clone, Copy)]
+struct Token<'a> {
+    line: usize,     // zero-based
+    within: &'a str, // the whole line
+    offset: usize,   // in bytes
+    text: &'a str,
+}
+
+impl Token<'_> {
+    fn error(&self, message: impl Into<String>) -> ParseTextError {
+        ParseTextError::at(self.line, self.within, self.offset, message)
+    }
+
+    fn parse<T: FromStr>(&self, what: &str) -> Result<T, ParseTextError> {
+        self.text
+            .parse()
+            .map_err(|_| self.error(format!("{}: bad {what}", self.text)))
+    }
+}
+
+/// Splits each significant line of a saved game into tokens.
+fn tokenize(s: &str) -> Vec<Vec<Token<'_>>> {
+    s.lines()
+        .enumerate()
+        .map(|(index, line)| {
+            let mut tokens = Vec::new();
+            let mut start = None;
+            for (offset, c) in line.char_indices().chain([(line.len(), ' ')]) {
+                match (start, c.is_whitespace()) {
+                    (None, false) => start = Some(offset),
+                    (Some(begin), true) => {
+                        tokens.push(Token {
+                            line: index,
+                            within: line,
+                            offset: begin,
+                            text: &line[begin..offset],
+                        });
+                        start = None;
+                    }
+                    _ => {}
+                }
+            }
+            tokens
+        })
+        .filter(|tokens| {
+            tokens
+                .first()
+                .is_some_and(|token| !token.text.starts_with('#'))
+        })
+        .collect()
+}
+
+/// Checks that a line starts with the specified keyword, and returns the
+/// tokens after it.
+fn expect_keyword<'a, 'b>(
+    tokens: Option<&'b [Token<'a>]>,
+    keyword: &str,
+    end: usize,
+) -> Result<(Token<'a>, &'b [Token<'a>]), ParseTextError> {
+    let missing = || ParseTextError::at(end, "", 0, format!("expected {keyword}"));
+    let (first, rest) = tokens.and_then(<[_]>::split_first).ok_or_else(missing)?;
+    if first.text != keyword {
+        return Err(first.error(format!("{}: expected {keyword}", first.text)));
+    }
+    Ok((*first, rest))
+}
+
+/// Parses a line holding a keyword followed by exactly one value, returning
+/// the keyword token (for reporting any later problem with the value) and the
+/// value.
+fn parse_setting<'a, T: FromStr>(
+    tokens: Option<&[Token<'a>]>,
+    keyword: &str,
+    what: &str,
+    end: usize,
+) -> Result<(Token<'a>, T), ParseTextError> {
+    let (first, rest) = expect_keyword(tokens, keyword, end)?;
+    match rest {
+        [value] => Ok((first, value.parse(what)?)),
+        [] => Err(first.error(format!("{keyword}: expected {what}"))),
+        [_, extra, ..] => Err(extra.error(format!("{}: unexpected", extra.text))),
+    }
+}
+
+impl FromStr for SavedGame {
+    type Err = ParseTextError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = tokenize(s);
+        // The line on which to report anything missing from the end of the
+        // input, counting from zero.
+        let end = s.lines().count();
+        let mut lines = lines.iter().map(Vec::as_slice);
+
+        let (pegs_token, pegs): (_, usize) = parse_setting(lines.next(), "pegs", "peg count", end)?;
+        if !(3..=TowerSelector::MAX).contains(&pegs) {
+            return Err(pegs_token.error(format!(
+                "peg count should be from 3 to {}: {pegs}",
+                TowerSelector::MAX
+            )));
+        }
+        let (target_token, target): (_, TowerSelector) =
+            parse_setting(lines.next(), "target", "tower selector", end)?;
+        if target.0 >= pegs {
+            return Err(target_token.error(format!("there is no tower {target}")));
+        }
+
+        let mut towers = TowerSet::with_pegs(pegs, 0);
+        let mut radii = Vec::new(); // every radius, and where it was found
+        for selector in (0..pegs).map(TowerSelector) {
+            let (_, disks) = expect_keyword(lines.next(), &selector.to_string(), end)?;
+            let mut tower = Tower::default();
+            for token in disks {
+                let radius: usize = token.parse("disk radius")?;
+                if radius == 0 {
+                    return Err(token.error("disk radius should be positive"));
+                }
+                if let Some(below) = tower.top().filter(|below| below.radius < radius) {
+                    return Err(token.error(format!(
+                        "disk {radius} cannot rest on smaller disk {}",
+                        below.radius
+                    )));
+                }
+                tower.push(Disk { radius, colour: 0 });
+                radii.push((radius, *token));
+            }
+            towers[selector] = tower;
+        }
+        radii.sort_by_key(|&(radius, _)| radius);
+        for (index, &(radius, token)) in radii.iter().enumerate() {
+            if radius != index + 1 {
+                let message = if radius == index {
+                    format!("disk {radius} appears more than once")
+                } else {
+                    format!("disk {} is missing", index + 1)
+                };
+                return Err(token.error(message));
+            }
+        }
+
+        let mut history = Vec::new();
+        if let Some(tokens) = lines.next() {
+            let (_, moves) = expect_keyword(Some(tokens), "moves", end)?;
+            for token in moves {
+                let next = parse_move(token.text)
+                    .ok_or_else(|| token.error(format!("{}: bad move", token.text)))?;
+                history.push((next, *token));
+            }
+        }
+        if let Some(extra) = lines.next().and_then(<[_]>::first) {
+            return Err(extra.error(format!("{}: unexpected", extra.text)));
+        }
+
+        // Check that the moves could have led to the towers, by taking them
+        // back one at a time.
+        let mut start = towers.clone();
+        for &((source, target), token) in history.iter().rev() {
+            start
+                .move_disk(target, source)
+                .map_err(|err| token.error(format!("{}: cannot be undone: {err}", token.text)))?;
+        }
+
+        Ok(SavedGame {
+            towers,
+            target,
+            history: history.into_iter().map(|(next, _)| next).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const A: TowerSelector = TowerSelector::A;
+    const B: TowerSelector = TowerSelector::B;
+    const C: TowerSelector = TowerSelector::C;
+
+    fn parse_error(s: &str) -> String {
+        s.parse::<SavedGame>().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let text = "pegs 3\ntarget B\nA 3\nB 2\nC 1\nmoves AC AB\n";
+        let game: SavedGame = text.parse().unwrap();
+        assert_eq!(game.towers, TowerSet::from_positions(3, &[C, B, A]));
+        assert_eq!(game.target, B);
+        assert_eq!(game.history, [(A, C), (A, B)]);
+        assert_eq!(game.start(), TowerSet::with_pegs(3, 3));
+        assert_eq!(game.to_string(), text);
+
+        let text = "pegs 4\ntarget D\nA 2 1\nB\nC\nD\n";
+        let game: SavedGame = text.parse().unwrap();
+        assert_eq!(game.towers, TowerSet::with_pegs(4, 2));
+        assert_eq!(game.to_string(), text);
+    }
+
+    #[test]
+    fn test_comments_and_whitespace() {
+        let text = "# A workshop game.\n\n  pegs   3\ntarget C\nA\nB 1\n\nC\n# No moves yet.\n";
+        let game: SavedGame = text.parse().unwrap();
+        assert_eq!(game.towers, TowerSet::from_positions(3, &[B]));
+        assert_eq!(game.target, C);
+        assert!(game.history.is_empty());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse_error(""), "1:1: expected pegs");
+        assert_eq!(parse_error("pegs three"), "1:6: three: bad peg count");
+        assert_eq!(
+            parse_error("pegs 2"),
+            "1:1: peg count should be from 3 to 26: 2"
+        );
+        assert_eq!(parse_error("pegs 3 4"), "1:8: 4: unexpected");
+        assert_eq!(
+            parse_error("pegs 3\ntarget"),
+            "2:1: target: expected tower selector"
+        );
+        assert_eq!(parse_error("pegs 3\ntarget D"), "2:1: there is no tower D");
+        assert_eq!(
+            parse_error("pegs 3\ntarget B\nA 1\nC"),
+            "4:1: C: expected B"
+        );
+        assert_eq!(parse_error("pegs 3\ntarget B\nA 1\nB"), "5:1: expected C");
+        assert_eq!(
+            parse_error("pegs 3\ntarget B\nA 1 2\nB\nC"),
+            "3:5: disk 2 cannot rest on smaller disk 1"
+        );
+        assert_eq!(
+            parse_error("pegs 3\ntarget B\nA 3 1\nB\nC"),
+            "3:3: disk 2 is missing"
+        );
+        assert_eq!(
+            parse_error("pegs 3\ntarget B\nA 2 1\nB 1\nC"),
+            "4:3: disk 1 appears more than once"
+        );
+        assert_eq!(
+            parse_error("pegs 3\ntarget B\nA x\nB\nC"),
+            "3:3: x: bad disk radius"
+        );
+        assert_eq!(
+            parse_error("pegs 3\ntarget B\nA 0\nB\nC"),
+            "3:3: disk radius should be positive"
+        );
+        assert_eq!(
+            parse_error("pegs 3\ntarget B\nA 2 1\nB\nC\nmoves AB  A"),
+            "6:11: A: bad move"
+        );
+        assert_eq!(
+            parse_error("pegs 3\ntarget B\nA 2 1\nB\nC\nmoves AB\u{3000}A"),
+            "6:10: A: bad move"
+        );
+        assert_eq!(
+            parse_error("pegs 3\ntarget B\nA 2 1\nB\nC\nmoves AB"),
+            "6:7: AB: cannot be undone: tower B is empty"
+        );
+        assert_eq!(
+            parse_error("pegs 3\ntarget B\nA 2 1\nB\nC\nmoves\nmore"),
+            "7:1: more: unexpected"
+        );
+    }
+}
@@ -0,0 +1,460 @@
+//! Scalable vector graphics of the towers, for slides.  A picture is either a
+//! single frame, or an animation of a list of moves, drawn with SMIL
+//! `<animate>` elements so that it needs no script or style sheet.  Each move
+//! takes the same time, split evenly between lifting the disk above the pegs,
+//! carrying it across, and lowering it onto the target.
+
+use std::fmt::{self, Write};
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::{IllegalMoveError, Move, TowerSelector, TowerSet};
+
+/// Colours, size and timing of a picture.  Colours may be anything SVG
+/// understands, such as `navy` or `#336699`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SvgStyle {
+    /// The width of the picture, in pixels.
+    pub width: u32,
+    /// The height of the picture, in pixels.
+    pub height: u32,
+    /// The time each move takes, when animated.
+    pub move_duration: Duration,
+    /// The colour behind the towers.
+    pub background: String,
+    /// The colour of the pegs and their base.
+    pub pegs: String,
+    /// The colours of the disks, from the smallest up, repeating as needed.
+    /// If the disks have colours of their own, as in the bicolour and
+    /// multi-copy variants, these are used in the order of those colours
+    /// instead.
+    pub disks: Vec<String>,
+}
+
+impl Default for SvgStyle {
+    fn default() -> Self {
+        SvgStyle {
+            width: 640,
+            height: 240,
+            move_duration: Duration::from_millis(500),
+            background: "white".to_string(),
+            pegs: "#555555".to_string(),
+            disks: [
+                "#e6194b", "#3cb44b", "#ffe119", "#4363d8", "#f58231", "#911eb4",
+            ]
+            .map(String::from)
+            .to_vec(),
+        }
+    }
+}
+
+/// Which picture to draw.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SvgFrame {
+    /// The towers before any moves.
+    First,
+    /// The towers after every move.
+    Last,
+    /// Every move, one after another.
+    Animated,
+}
+
+impl FromStr for SvgFrame {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first" => Ok(SvgFrame::First),
+            "last" => Ok(SvgFrame::Last),
+            "animated" => Ok(SvgFrame::Animated),
+            _ => Err(format!("{s}: bad frame")),
+        }
+    }
+}
+
+/// Escapes text for use in an attribute value.
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Where things go in a picture, in pixels from the top left.
+struct Layout {
+    disk_count: usize,
+    largest_radius: usize,
+    column: f64,      // width of the space around each peg
+    margin: f64,      // above the pegs and below the base
+    base: f64,        // top of the base
+    disk_height: f64, // height of every disk
+    lift: f64,        // top of a disk being carried between pegs
+}
+
+impl Layout {
+    fn new(towers: &TowerSet, style: &SvgStyle) -> Layout {
+        let disk_count = towers.disks().count();
+        let largest_radius = towers
+            .disks()
+            .map(|disk| disk.radius)
+            .max()
+            .unwrap_or_default();
+        let (width, height) = (style.width as f64, style.height as f64);
+        let column = width / towers.towers.len() as f64;
+        let margin = height * 0.05;
+        let base = height - 2.0 * margin;
+        // Leave room above the pegs to carry the disks across.
+        let disk_height = ((base - margin) / (disk_count + 2) as f64).min(column * 0.15);
+        let peg_top = base - (disk_count as f64 + 0.5) * disk_height;
+        Layout {
+            disk_count,
+            largest_radius,
+            column,
+            margin,
+            base,
+            disk_height,
+            lift: peg_top - 1.25 * disk_height,
+        }
+    }
+
+    fn centre(&self, peg: TowerSelector) -> f64 {
+        self.column * (peg.0 as f64 + 0.5)
+    }
+
+    fn disk_width(&self, radius: usize) -> f64 {
+        let (smallest, largest) = (self.column * 0.2, self.column * 0.9);
+        match self.largest_radius {
+            0 | 1 => largest,
+            n => smallest + (largest - smallest) * (radius - 1) as f64 / (n - 1) as f64,
+        }
+    }
+
+    /// Returns the top left of a disk on the specified peg, with the
+    /// specified number of disks below it.
+    fn disk_position(&self, radius: usize, peg: TowerSelector, below: usize) -> (f64, f64) {
+        (
+            self.centre(peg) - self.disk_width(radius) / 2.0,
+            self.base - (below + 1) as f64 * self.disk_height,
+        )
+    }
+}
+
+/// Writes the background, base, pegs and labels.
+fn write_scenery(
+    svg: &mut String,
+    towers: &TowerSet,
+    layout: &Layout,
+    style: &SvgStyle,
+) -> fmt::Result {
+    let (width, height) = (style.width, style.height);
+    writeln!(svg, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    )?;
+    writeln!(
+        svg,
+        r#"<rect width="{width}" height="{height}" fill="{}"/>"#,
+        escape(&style.background)
+    )?;
+    let pegs = escape(&style.pegs);
+    writeln!(
+        svg,
+        r#"<rect x="0" y="{:.1}" width="{width}" height="{:.1}" fill="{pegs}"/>"#,
+        layout.base,
+        layout.margin * 0.5
+    )?;
+    let peg_width = layout.disk_height * 0.4;
+    let peg_height = (layout.disk_count as f64 + 0.5) * layout.disk_height;
+    for selector in towers.selectors() {
+        let centre = layout.centre(selector);
+        writeln!(
+            svg,
+            r#"<rect x="{:.1}" y="{:.1}" width="{peg_width:.1}" height="{peg_height:.1}" fill="{pegs}"/>"#,
+            centre - peg_width / 2.0,
+            layout.base - peg_height,
+        )?;
+        writeln!(
+            svg,
+            r#"<text x="{centre:.1}" y="{:.1}" text-anchor="middle" font-family="sans-serif" font-size="{:.1}" fill="{pegs}">{selector}</text>"#,
+            style.height as f64 - layout.margin * 0.25,
+            layout.margin * 1.2,
+        )?;
+    }
+    Ok(())
+}
+
+/// Returns a picture of the towers after the moves, animated if requested.
+pub fn draw(
+    start: &TowerSet,
+    moves: &[Move],
+    frame: SvgFrame,
+    style: &SvgStyle,
+) -> Result<String, IllegalMoveError> {
+    let layout = Layout::new(start, style);
+    let by_colour = start.is_coloured();
+    let mut end = start.clone();
+    // The disks on each tower, from the bottom up, numbered in the order they
+    // are drawn at the start, so that disks of the same size are told apart.
+    let mut numbers: Vec<Vec<usize>> = Vec::new();
+    for tower in start {
+        let first = numbers.iter().map(Vec::len).sum::<usize>();
+        numbers.push((first..first + tower.disks.len()).collect());
+    }
+    // The moves of each disk, as (time, from, to) in seconds and pixels.
+    let mut animations = vec![Vec::new(); layout.disk_count];
+    for (index, &(source, target)) in moves.iter().enumerate() {
+        end.move_disk(source, target)?;
+        let number = numbers[source.0].pop().expect("the move was legal");
+        numbers[target.0].push(number);
+        let radius = end[target]
+            .top()
+            .expect("a disk was just moved here")
+            .radius;
+        let from = layout.disk_position(radius, source, end[source].disks.len());
+        let to = layout.disk_position(radius, target, end[target].disks.len() - 1);
+        let time = style.move_duration.as_secs_f64() * index as f64;
+        animations[number].push((time, from, to));
+    }
+    let (towers, animated) = match frame {
+        SvgFrame::First => (start, false),
+        SvgFrame::Last => (&end, false),
+        SvgFrame::Animated => (start, true),
+    };
+
+    let mut svg = String::new();
+    let write = |svg: &mut String| -> fmt::Result {
+        write_scenery(svg, towers, &layout, style)?;
+        let phase = style.move_duration.as_secs_f64() / 3.0;
+        let mut number = 0;
+        for (selector, tower) in towers.selectors().zip(towers) {
+            for (below, disk) in tower.disks.iter().enumerate() {
+                let radius = disk.radius;
+                let (x, y) = layout.disk_position(radius, selector, below);
+                let colour = &style.disks[disk.shade(by_colour) % style.disks.len()];
+                write!(
+                    svg,
+                    r#"<rect x="{x:.1}" y="{y:.1}" width="{:.1}" height="{:.1}" rx="{:.1}" fill="{}">"#,
+                    layout.disk_width(radius),
+                    layout.disk_height * 0.9,
+                    layout.disk_height * 0.3,
+                    escape(colour),
+                )?;
+                for &(time, (x0, y0), (x1, y1)) in animations[number].iter().filter(|_| animated) {
+                    let steps = [
+                        ("y", y0, layout.lift, time),
+                        ("x", x0, x1, time + phase),
+                        ("y", layout.lift, y1, time + 2.0 * phase),
+                    ];
+                    for (attribute, from, to, begin) in steps {
+                        write!(
+                            svg,
+                            r#"<animate attributeName="{attribute}" from="{from:.1}" to="{to:.1}" begin="{begin:.3}s" dur="{phase:.3}s" fill="freeze"/>"#
+                        )?;
+                    }
+                }
+                writeln!(svg, "</rect>")?;
+                number += 1;
+            }
+        }
+        writeln!(svg, "</svg>")
+    };
+    write(&mut svg).expect("writing to a string cannot fail");
+    Ok(svg)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hanoi::Tower;
+    use std::collections::HashMap;
+
+    const A: TowerSelector = TowerSelector::A;
+    const B: TowerSelector = TowerSelector::B;
+    const C: TowerSelector = TowerSelector::C;
+
+    /// An element of a parsed document.
+    #[derive(Debug)]
+    struct Element {
+        name: String,
+        attributes: HashMap<String, String>,
+        children: Vec<Element>,
+    }
+
+    impl Element {
+        /// Returns every element with the specified name, at any depth.
+        fn find<'a>(&'a self, name: &str, found: &mut Vec<&'a Element>) {
+            if self.name == name {
+                found.push(self);
+            }
+            for child in &self.children {
+                child.find(name, found);
+            }
+        }
+    }
+
+    /// Parses enough of XML to check the pictures: a declaration, then
+    /// elements with quoted attributes, text, and the standard entities.
+    /// Panics if the document is not well formed.
+    fn parse_xml(xml: &str) -> Element {
+        let rest = xml.strip_prefix("<?xml").expect("declaration");
+        let mut rest = &rest[rest.find("?>").expect("end of declaration") + 2..];
+        let mut stack: Vec<Element> = Vec::new();
+        let mut root = None;
+        loop {
+            let text_end = rest.find('<').unwrap_or(rest.len());
+            let text = &rest[..text_end];
+            assert!(!text.contains('>'), "stray > in {text:?}");
+            let unescaped = text
+                .replace("&lt;", "")
+                .replace("&gt;", "")
+                .replace("&amp;", "")
+                .replace("&quot;", "");
+            assert!(!unescaped.contains('&'), "bad entity in {text:?}");
+            if stack.is_empty() {
+                assert!(text.trim().is_empty(), "text outside the root: {text:?}");
+            }
+            rest = &rest[text_end..];
+            if rest.is_empty() {
+                break;
+            }
+            let tag_end = rest.find('>').expect("end of tag");
+            let tag = &rest[1..tag_end];
+            rest = &rest[tag_end + 1..];
+            if let Some(name) = tag.strip_prefix('/') {
+                let element = stack.pop().expect("open element");
+                assert_eq!(element.name, name, "mismatched end tag");
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+                continue;
+            }
+            let (tag, empty) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let (name, mut attributes_text) = tag.split_once(' ').unwrap_or((tag, ""));
+            assert!(!name.is_empty() && name.chars().all(char::is_alphanumeric));
+            let mut attributes = HashMap::new();
+            while !attributes_text.trim().is_empty() {
+                let (key, value) = attributes_text.split_once("=\"").expect("quoted value");
+                let (value, after) = value.split_once('"').expect("closing quote");
+                assert!(!value.contains('<'), "< in attribute {key}");
+                let key = key.trim().to_string();
+                assert!(attributes.insert(key, value.to_string()).is_none());
+                attributes_text = after;
+            }
+            let element = Element {
+                name: name.to_string(),
+                attributes,
+                children: Vec::new(),
+            };
+            if empty {
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            } else {
+                stack.push(element);
+            }
+        }
+        assert!(stack.is_empty(), "unclosed elements");
+        root.expect("root element")
+    }
+
+    fn find<'a>(root: &'a Element, name: &str) -> Vec<&'a Element> {
+        let mut found = Vec::new();
+        root.find(name, &mut found);
+        found
+    }
+
+    #[test]
+    fn test_animated() {
+        let towers = TowerSet::with_pegs(3, 3);
+        let moves = [(A, B), (A, C), (B, C), (A, B), (C, A), (C, B), (A, B)];
+        let style = SvgStyle::default();
+        let root = parse_xml(&draw(&towers, &moves, SvgFrame::Animated, &style).unwrap());
+        assert_eq!(root.name, "svg");
+        assert_eq!(root.attributes["width"], "640");
+        // The background, the base, three pegs and three disks.
+        assert_eq!(find(&root, "rect").len(), 8);
+        assert_eq!(find(&root, "text").len(), 3);
+        let animations = find(&root, "animate");
+        assert_eq!(animations.len(), 3 * moves.len());
+        let last = animations
+            .iter()
+            .map(|animate| {
+                animate.attributes["begin"]
+                    .trim_end_matches('s')
+                    .parse::<f64>()
+                    .unwrap()
+            })
+            .fold(0.0, f64::max);
+        assert!((last - (6.0 + 2.0 / 3.0) * 0.5).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_static_frames() {
+        let towers = TowerSet::with_pegs(4, 2);
+        let moves = [(A, C), (A, B), (C, B)];
+        let style = SvgStyle {
+            width: 400,
+            height: 100,
+            background: "\"quoted\" & <odd>".to_string(),
+            disks: vec!["red".to_string()],
+            ..SvgStyle::default()
+        };
+        for frame in [SvgFrame::First, SvgFrame::Last] {
+            let root = parse_xml(&draw(&towers, &moves, frame, &style).unwrap());
+            assert_eq!(root.attributes["viewBox"], "0 0 400 100");
+            assert!(find(&root, "animate").is_empty());
+            let disks: Vec<_> = find(&root, "rect")
+                .into_iter()
+                .filter(|rect| rect.attributes["fill"] == "red")
+                .collect();
+            assert_eq!(disks.len(), 2);
+            // The disks start on the first of four pegs, and end on the
+            // second.
+            let peg = if frame == SvgFrame::First { 0.0 } else { 1.0 };
+            for disk in disks {
+                let x: f64 = disk.attributes["x"].parse().unwrap();
+                let width: f64 = disk.attributes["width"].parse().unwrap();
+                assert!((x + width / 2.0 - 100.0 * (peg + 0.5)).abs() < 0.1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_copies_move_apart() {
+        let mut towers = TowerSet::with_pegs(3, 0);
+        towers[A] = Tower::with_copies(1, 2);
+        let moves = [(A, B), (A, C)];
+        let style = SvgStyle::default();
+        let root = parse_xml(&draw(&towers, &moves, SvgFrame::Animated, &style).unwrap());
+        // Each copy is filled by its colour, and makes only its own move.
+        for (colour, peg) in [(0, 2.0), (1, 1.0)] {
+            let disks: Vec<_> = find(&root, "rect")
+                .into_iter()
+                .filter(|rect| rect.attributes["fill"] == style.disks[colour])
+                .collect();
+            assert_eq!(disks.len(), 1);
+            // Both copies are the largest disk, so take the widest space.
+            let width: f64 = disks[0].attributes["width"].parse().unwrap();
+            assert!((width - 640.0 / 3.0 * 0.9).abs() < 0.1);
+            let animations = find(disks[0], "animate");
+            assert_eq!(animations.len(), 3);
+            let x: f64 = animations[1].attributes["to"].parse().unwrap();
+            assert!((x + width / 2.0 - 640.0 / 3.0 * (peg + 0.5)).abs() < 0.1);
+        }
+    }
+
+    #[test]
+    fn test_illegal_move() {
+        let towers = TowerSet::with_pegs(3, 2);
+        assert_eq!(
+            draw(&towers, &[(B, A)], SvgFrame::Animated, &SvgStyle::default()),
+            Err(IllegalMoveError::EmptyTower(B))
+        );
+    }
+}
@@ -0,0 +1,255 @@
+//! A record of every call the recursive solution makes, to show how the
+//! recursion unfolds.  Each call to solve a tower of height n makes two calls
+//! to solve a tower of height n - 1, with a move between them, so the calls
+//! form a binary tree 2^(n+1) - 1 frames in size, whose 2^n leaves are the
+//! calls that reach the base case and move nothing.  Reading the moves in
+//! the order the calls make them gives the solution.
+//!
+//! The calls are recorded from the `steps` of the same recursive solution
+//! that `moves` and `solve` follow, so the trace always shows what they do.
+
+use std::fmt::{self, Write};
+
+use super::{steps, Move, Step, TowerSelectorSet};
+
+/// One call of the recursive solution.
+pub struct Frame {
+    /// The number of disks to move, which the book calls `numberOfDisks`.
+    pub height: usize,
+    /// The towers the call moves the disks between.
+    pub selectors: TowerSelectorSet,
+    /// The number of calls above this one on the stack.
+    pub depth: usize,
+    /// The move this call made, if any, and its number in the solution,
+    /// counting from 1.
+    pub made: Option<(u64, Move)>,
+    /// The frames of the calls this one made, in order.
+    pub calls: Vec<usize>,
+}
+
+impl Frame {
+    /// Returns the call as the book would write it.
+    fn label(&self) -> String {
+        let TowerSelectorSet {
+            source,
+            target,
+            buffer,
+        } = self.selectors;
+        format!("solve({}, {source}, {target}, {buffer})", self.height)
+    }
+}
+
+/// The frames of every call of the recursive solution, in the order they
+/// were called, so the first is the call that solves the whole tower.
+pub struct Trace {
+    frames: Vec<Frame>,
+    moves: u64,
+}
+
+impl Trace {
+    /// Solves a tower of the specified height, recording each call.
+    pub fn new(height: usize, selectors: TowerSelectorSet) -> Trace {
+        let mut trace = Trace {
+            frames: Vec::new(),
+            moves: 0,
+        };
+        // The frame of the call at each depth, along the path to the latest.
+        let mut path: Vec<usize> = Vec::new();
+        for step in steps(height, selectors) {
+            match step {
+                Step::Call {
+                    height,
+                    selectors,
+                    depth,
+                } => {
+                    let frame = trace.frames.len();
+                    path.truncate(depth);
+                    if let Some(&caller) = path.last() {
+                        trace.frames[caller].calls.push(frame);
+                    }
+                    path.push(frame);
+                    trace.frames.push(Frame {
+                        height,
+                        selectors,
+                        depth,
+                        made: None,
+                        calls: Vec::new(),
+                    });
+                }
+                Step::Move { made, depth } => {
+                    trace.moves += 1;
+                    trace.frames[path[depth]].made = Some((trace.moves, made));
+                }
+            }
+        }
+        trace
+    }
+
+    /// Returns the number of calls and moves at each depth, from the first
+    /// call down.
+    pub fn depth_counts(&self) -> Vec<(usize, u64)> {
+        let mut counts = Vec::new();
+        for frame in &self.frames {
+            if counts.len() <= frame.depth {
+                counts.resize(frame.depth + 1, (0, 0));
+            }
+            counts[frame.depth].0 += 1;
+            counts[frame.depth].1 += frame.made.is_some() as u64;
+        }
+        counts
+    }
+
+    /// Writes the calls as an indented tree, each call above the calls it
+    /// makes, with each move between the two calls it separates.
+    pub fn write_tree(&self, out: &mut impl Write) -> fmt::Result {
+        if !self.frames.is_empty() {
+            self.write_subtree(out, 0)?;
+        }
+        Ok(())
+    }
+
+    fn write_subtree(&self, out: &mut impl Write, index: usize) -> fmt::Result {
+        let frame = &self.frames[index];
+        let indent = "    ".repeat(frame.depth);
+        writeln!(out, "{indent}{}", frame.label())?;
+        for (order, &call) in frame.calls.iter().enumerate() {
+            if let (1, Some((number, (source, target)))) = (order, frame.made) {
+                writeln!(out, "{indent}    Move {number}: {source}{target}")?;
+            }
+            self.write_subtree(out, call)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a table of the number of calls and moves at each depth.
+    pub fn write_depth_table(&self, out: &mut impl Write) -> fmt::Result {
+        writeln!(out, "Depth  Height  Calls  Moves")?;
+        for (depth, (calls, moves)) in self.depth_counts().into_iter().enumerate() {
+            let height = self.frames[0].height - depth;
+            writeln!(out, "{depth:>5}  {height:>6}  {calls:>5}  {moves:>5}")?;
+        }
+        writeln!(
+            out,
+            "Total  {:>6}  {:>5}  {:>5}",
+            "",
+            self.frames.len(),
+            self.moves
+        )
+    }
+
+    /// Writes the tree of calls in the DOT language of Graphviz, each call
+    /// labelled with the move it made, if any.
+    pub fn write_dot(&self, out: &mut impl Write) -> fmt::Result {
+        writeln!(out, "digraph recursion {{")?;
+        writeln!(out, "    ordering=out;")?;
+        writeln!(out, "    node [shape=box, fontname=monospace];")?;
+        for (index, frame) in self.frames.iter().enumerate() {
+            match frame.made {
+                Some((number, (source, target))) => writeln!(
+                    out,
+                    "    f{index} [label=\"{}\\nMove {number}: {source}{target}\"];",
+                    frame.label()
+                )?,
+                None => writeln!(out, "    f{index} [label=\"{}\"];", frame.label())?,
+            }
+        }
+        for (index, frame) in self.frames.iter().enumerate() {
+            for call in &frame.calls {
+                writeln!(out, "    f{index} -> f{call};")?;
+            }
+        }
+        writeln!(out, "}}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hanoi::moves;
+
+    #[test]
+    fn test_frames() {
+        for height in 0..=6 {
+            let trace = Trace::new(height, TowerSelectorSet::new());
+            assert_eq!(trace.frames.len(), (1 << (height + 1)) - 1);
+            for frame in &trace.frames {
+                assert_eq!(frame.height + frame.depth, height);
+                assert_eq!(frame.calls.len(), if frame.height == 0 { 0 } else { 2 });
+            }
+
+            // The moves, in the order they were numbered, are the solution.
+            let mut made: Vec<_> = trace.frames.iter().filter_map(|frame| frame.made).collect();
+            made.sort_by_key(|&(number, _)| number);
+            let made: Vec<_> = made.into_iter().map(|(_, next)| next).collect();
+            assert_eq!(
+                made,
+                moves(height, TowerSelectorSet::new()).collect::<Vec<_>>()
+            );
+
+            let counts = trace.depth_counts();
+            assert_eq!(counts.len(), height + 1);
+            for (depth, &(calls, moves)) in counts.iter().enumerate() {
+                assert_eq!(calls, 1 << depth);
+                assert_eq!(moves, if depth < height { 1 << depth } else { 0 });
+            }
+        }
+    }
+
+    #[test]
+    fn test_tree() {
+        let mut tree = String::new();
+        Trace::new(2, TowerSelectorSet::new())
+            .write_tree(&mut tree)
+            .unwrap();
+        let want = [
+            "solve(2, A, B, C)",
+            "    solve(1, A, C, B)",
+            "        solve(0, A, B, C)",
+            "        Move 1: AC",
+            "        solve(0, B, C, A)",
+            "    Move 2: AB",
+            "    solve(1, C, B, A)",
+            "        solve(0, C, A, B)",
+            "        Move 3: CB",
+            "        solve(0, A, B, C)",
+        ];
+        assert_eq!(tree, want.join("\n") + "\n");
+    }
+
+    #[test]
+    fn test_depth_table() {
+        let mut table = String::new();
+        Trace::new(3, TowerSelectorSet::new())
+            .write_depth_table(&mut table)
+            .unwrap();
+        let want = [
+            "Depth  Height  Calls  Moves",
+            "    0       3      1      1",
+            "    1       2      2      2",
+            "    2       1      4      4",
+            "    3       0      8      0",
+            "Total             15      7",
+        ];
+        assert_eq!(table, want.join("\n") + "\n");
+    }
+
+    #[test]
+    fn test_dot() {
+        let mut dot = String::new();
+        Trace::new(1, TowerSelectorSet::new())
+            .write_dot(&mut dot)
+            .unwrap();
+        let want = [
+            "digraph recursion {",
+            "    ordering=out;",
+            "    node [shape=box, fontname=monospace];",
+            "    f0 [label=\"solve(1, A, B, C)\\nMove 1: AB\"];",
+            "    f1 [label=\"solve(0, A, C, B)\"];",
+            "    f2 [label=\"solve(0, C, B, A)\"];",
+            "    f0 -> f1;",
+            "    f0 -> f2;",
+            "}",
+        ];
+        assert_eq!(dot, want.join("\n") + "\n");
+    }
+}
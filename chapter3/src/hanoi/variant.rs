@@ -0,0 +1,371 @@
+//! Puzzles with more than one disk of each size, told apart by colour.  A disk
+//! may rest on another of the same size, so a group of equal disks can move
+//! one at a time like a single disk, except that it ends up upside down:
+//!
+//! - With k copies of each size, a tower of n sizes takes k(2^n - 1) moves,
+//!   if equal disks may finish in any order.
+//! - If equal disks must finish in the order they started in, each group must
+//!   be turned upside down an even number of times.  With k copies of each
+//!   size, the solution here takes k·2^(n+1) - 2k - 1 moves, or 2^(n+2) - 5
+//!   with two copies.  A search of every state finds none shorter with two
+//!   copies of up to four sizes, or three copies of up to three sizes, but
+//!   it is not known to be the fewest beyond those.
+//! - In the bicolor puzzle, each size has a white disk below a black one, and
+//!   the colours must be separated: the white disks onto the target tower,
+//!   and the black disks onto the buffer.
+//!
+//! The bicolor puzzle is solved by breadth-first search over every state, so
+//! any goal can be reached in the fewest moves, if slowly.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::repeat_n;
+use std::str::FromStr;
+
+use super::rules::Rules;
+use super::{moves, Move, Tower, TowerSelector, TowerSelectorSet, TowerSet};
+
+/// The most colours, each named by a letter of the alphabet.
+pub const MAX_COLOURS: usize = 26;
+
+/// The most sizes whose states can be searched for a solution in reasonable
+/// time.
+pub const MAX_SEARCH_HEIGHT: usize = 5;
+
+/// A set of disks to start with, and a goal for them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Variant {
+    /// This many disks of each size, which may finish in any order.
+    Copies(usize),
+    /// This many disks of each size, which must finish in their starting
+    /// order.
+    OrderedCopies(usize),
+    /// A white and a black disk of each size, to be separated.
+    Bicolor,
+}
+
+impl Variant {
+    /// Returns the number of disks of each size.
+    pub fn copies(self) -> usize {
+        match self {
+            Variant::Copies(copies) | Variant::OrderedCopies(copies) => copies,
+            Variant::Bicolor => 2,
+        }
+    }
+
+    /// Returns whether solutions are found by searching every state.
+    pub fn is_searched(self) -> bool {
+        self == Variant::Bicolor
+    }
+
+    /// Returns three towers, the source holding the disks of each size from
+    /// the largest up, coloured in order from the bottom of each group.
+    pub fn start(self, height: usize, source: TowerSelector) -> TowerSet {
+        let mut towers = TowerSet::with_pegs(3, 0);
+        towers[source] = Tower::with_copies(height, self.copies());
+        towers
+    }
+
+    /// Returns the goal of the variant, given the towers the disks start on,
+    /// end on, and pass through.
+    pub fn goal(self, selectors: TowerSelectorSet) -> Goal {
+        match self {
+            Variant::Copies(_) => Goal::Gather(selectors.target),
+            Variant::OrderedCopies(_) => Goal::GatherInOrder(selectors.target),
+            Variant::Bicolor => Goal::SortByColour(vec![selectors.target, selectors.buffer]),
+        }
+    }
+
+    /// Returns moves that reach the goal from the start, which are the
+    /// fewest, except perhaps for ordered copies (see the module notes).
+    /// Except in the bicolor puzzle, whose moves are found by searching, the
+    /// moves are made lazily, like those of `moves`.
+    pub fn moves(
+        self,
+        height: usize,
+        selectors: TowerSelectorSet,
+    ) -> Box<dyn Iterator<Item = Move>> {
+        match self {
+            Variant::Copies(copies) => Box::new(copies_moves(height, copies, selectors)),
+            Variant::OrderedCopies(1) => Box::new(moves(height, selectors)),
+            Variant::OrderedCopies(copies) => {
+                Box::new(ordered_copies_moves(height, copies, selectors))
+            }
+            Variant::Bicolor => {
+                let start = self.start(height, selectors.source);
+                let found = search(&start, &self.goal(selectors), &Rules::Classic)
+                    .expect("bicolor towers can always be separated");
+                Box::new(found.into_iter())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Variant::Copies(copies) => write!(f, "copies:{copies}"),
+            Variant::OrderedCopies(copies) => write!(f, "ordered-copies:{copies}"),
+            Variant::Bicolor => write!(f, "bicolor"),
+        }
+    }
+}
+
+impl FromStr for Variant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let copies = |count: &str| {
+            count
+                .parse()
+                .ok()
+                .filter(|copies| (1..=MAX_COLOURS).contains(copies))
+        };
+        let variant = match s.split_once(':') {
+            None if s == "bicolor" => Some(Variant::Bicolor),
+            Some(("copies", count)) => copies(count).map(Variant::Copies),
+            Some(("ordered-copies", count)) => copies(count).map(Variant::OrderedCopies),
+            _ => None,
+        };
+        variant.ok_or_else(|| format!("{s}: bad variant"))
+    }
+}
+
+/// What the towers must look like for the puzzle to be solved.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Goal {
+    /// Every disk on this tower, with equal disks in any order.
+    Gather(TowerSelector),
+    /// Every disk on this tower, with equal disks in order of colour from
+    /// the bottom up.
+    GatherInOrder(TowerSelector),
+    /// The disks of each colour on their own tower, indexed by colour.
+    SortByColour(Vec<TowerSelector>),
+}
+
+impl Goal {
+    /// Returns whether the towers meet the goal.
+    pub fn is_met(&self, towers: &TowerSet) -> bool {
+        let everything = towers.disks().count();
+        match self {
+            Goal::Gather(target) => towers[*target].disks.len() == everything,
+            Goal::GatherInOrder(target) => {
+                let disks = &towers[*target].disks;
+                disks.len() == everything
+                    && disks.windows(2).all(|pair| {
+                        pair[0].radius > pair[1].radius || pair[0].colour < pair[1].colour
+                    })
+            }
+            Goal::SortByColour(targets) => {
+                towers.selectors().zip(towers).all(|(selector, tower)| {
+                    tower
+                        .disks
+                        .iter()
+                        .all(|disk| targets.get(disk.colour) == Some(&selector))
+                })
+            }
+        }
+    }
+}
+
+/// Returns the fewest moves that take a tower with `copies` disks of each
+/// size from the source tower to the target tower, in any order.  Each move of
+/// the recursive solution becomes a move of every disk in a group.
+fn copies_moves(
+    height: usize,
+    copies: usize,
+    selectors: TowerSelectorSet,
+) -> impl Iterator<Item = Move> {
+    moves(height, selectors).flat_map(move |next| repeat_n(next, copies))
+}
+
+/// Returns moves that take a tower with `copies` disks of each size from the
+/// source tower to the target tower, keeping equal disks in order.  The
+/// recursive call comes last, between the same towers, so the calls unroll
+/// into a sequence, one for each size from the largest down.
+fn ordered_copies_moves(
+    height: usize,
+    copies: usize,
+    selectors: TowerSelectorSet,
+) -> impl Iterator<Item = Move> {
+    let TowerSelectorSet {
+        source,
+        target,
+        buffer,
+    } = selectors;
+    let back = TowerSelectorSet::from_parts(target, source, buffer);
+    // RECURSIVE CASE: The smaller groups move out of the way and back,
+    // turning upside down twice, while the largest group moves to the target
+    // by way of the buffer, also turning upside down twice.  Then the smaller
+    // groups move on top of it, in order.
+    let recursive = (2..=height).rev().flat_map(move |height| {
+        copies_moves(height - 1, copies, selectors)
+            .chain(repeat_n((source, buffer), copies))
+            .chain(copies_moves(height - 1, copies, back))
+            .chain(repeat_n((buffer, target), copies))
+    });
+    // BASE CASE: One group, which turns upside down on the buffer, except for
+    // its bottom disk, and then the right way up on the target.  With no
+    // disks at all, there is no group.
+    let groups = height.min(1);
+    let base = repeat_n((source, buffer), (copies - 1) * groups)
+        .chain(repeat_n((source, target), groups))
+        .chain(repeat_n((buffer, target), (copies - 1) * groups));
+    recursive.chain(base)
+}
+
+/// Returns the fewest moves from the start to any towers that meet the goal,
+/// under the rules, found by breadth-first search, or None if the goal cannot
+/// be met.
+pub fn search(start: &TowerSet, goal: &Goal, rules: &Rules) -> Option<Vec<Move>> {
+    // Every state found so far, in the order found, with the state and move
+    // before it.
+    let mut states = vec![(start.clone(), None)];
+    let mut numbers = HashMap::from([(start.clone(), 0)]);
+    let mut current = 0;
+    while let Some((towers, _)) = states.get(current).cloned() {
+        if goal.is_met(&towers) {
+            let mut path = Vec::new();
+            while let Some((before, last)) = states[current].1 {
+                path.push(last);
+                current = before;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for source in towers.selectors() {
+            for target in towers.selectors() {
+                let mut next = towers.clone();
+                if next.move_disk_with(rules, source, target).is_ok()
+                    && !numbers.contains_key(&next)
+                {
+                    numbers.insert(next.clone(), states.len());
+                    states.push((next, Some((current, (source, target)))));
+                }
+            }
+        }
+        current += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hanoi::Disk;
+
+    const A: TowerSelector = TowerSelector::A;
+    const B: TowerSelector = TowerSelector::B;
+    const C: TowerSelector = TowerSelector::C;
+
+    /// Checks that the moves of the variant are legal and reach its goal, and
+    /// that no fewer moves would, and returns how many there are.
+    fn check(variant: Variant, height: usize) -> usize {
+        let selectors = TowerSelectorSet::new();
+        let goal = variant.goal(selectors);
+        let mut towers = variant.start(height, A);
+        let moves: Vec<_> = variant.moves(height, selectors).collect();
+        for &(source, target) in &moves {
+            towers.move_disk(source, target).unwrap();
+        }
+        assert!(goal.is_met(&towers), "{variant} with {height} sizes");
+        let fewest = search(&variant.start(height, A), &goal, &Rules::Classic);
+        assert_eq!(fewest.map(|moves| moves.len()), Some(moves.len()));
+        moves.len()
+    }
+
+    #[test]
+    fn test_copies() {
+        for copies in 1..=3 {
+            for height in 0..=3 {
+                let want = copies * ((1 << height) - 1);
+                assert_eq!(check(Variant::Copies(copies), height), want);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ordered_copies() {
+        assert_eq!(check(Variant::OrderedCopies(1), 3), 7);
+        assert_eq!(check(Variant::OrderedCopies(2), 0), 0);
+        for height in 1..=4 {
+            assert_eq!(
+                check(Variant::OrderedCopies(2), height),
+                (1 << (height + 2)) - 5
+            );
+        }
+        for height in 1..=3 {
+            assert_eq!(
+                check(Variant::OrderedCopies(3), height),
+                (3 << (height + 1)) - 7
+            );
+        }
+    }
+
+    #[test]
+    fn test_moves_are_lazy() {
+        // Listing 3(2^40 - 1) moves would exhaust memory, but taking a few
+        // does not.
+        let selectors = TowerSelectorSet::new();
+        let first: Vec<_> = Variant::Copies(3).moves(40, selectors).take(4).collect();
+        assert_eq!(first, [(A, C), (A, C), (A, C), (A, B)]);
+        let first: Vec<_> = Variant::OrderedCopies(3)
+            .moves(40, selectors)
+            .take(3)
+            .collect();
+        assert_eq!(first, [(A, B), (A, B), (A, B)]);
+    }
+
+    #[test]
+    fn test_bicolor() {
+        let wants = [0, 2, 8, 23];
+        for (height, want) in wants.into_iter().enumerate() {
+            assert_eq!(check(Variant::Bicolor, height), want);
+        }
+    }
+
+    #[test]
+    fn test_goals() {
+        let disk = |radius, colour| Disk { radius, colour };
+        let mut towers = TowerSet::with_pegs(3, 0);
+        towers[B].disks = vec![disk(2, 0), disk(2, 1), disk(1, 1), disk(1, 0)];
+        assert!(Goal::Gather(B).is_met(&towers));
+        assert!(!Goal::Gather(A).is_met(&towers));
+        assert!(!Goal::GatherInOrder(B).is_met(&towers));
+        towers[B].disks.swap(2, 3);
+        assert!(Goal::GatherInOrder(B).is_met(&towers));
+
+        let mut towers = TowerSet::with_pegs(3, 0);
+        towers[C].disks = vec![disk(2, 0), disk(1, 0)];
+        towers[A].disks = vec![disk(1, 1)];
+        assert!(Goal::SortByColour(vec![C, A]).is_met(&towers));
+        assert!(!Goal::SortByColour(vec![C, B]).is_met(&towers));
+        assert!(!Goal::SortByColour(vec![C]).is_met(&towers));
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let towers = Variant::Copies(1).start(2, A);
+        let rules: Rules = "AB,BC".parse().unwrap();
+        assert_eq!(search(&towers, &Goal::Gather(A), &rules), Some(vec![]));
+        assert_eq!(search(&towers, &Goal::Gather(C), &rules), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("bicolor".parse(), Ok(Variant::Bicolor));
+        assert_eq!("copies:3".parse(), Ok(Variant::Copies(3)));
+        assert_eq!("ordered-copies:2".parse(), Ok(Variant::OrderedCopies(2)));
+        assert_eq!(Variant::OrderedCopies(2).to_string(), "ordered-copies:2");
+        for bad in [
+            "",
+            "copies",
+            "copies:0",
+            "copies:27",
+            "bicolor:2",
+            "tricolor",
+        ] {
+            assert_eq!(bad.parse::<Variant>(), Err(format!("{bad}: bad variant")));
+        }
+    }
+}
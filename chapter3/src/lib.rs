@@ -0,0 +1,4 @@
+#![warn(missing_docs)]
+//! Code shared by the programs of Part 3 of The Recursive Book of Recursion.
+
+pub mod hanoi;
@@ -0,0 +1,135 @@
+//! Tests of the `hanoi` module through its public interface, as another
+//! program would use it.
+
+use std::io::Cursor;
+
+use part3::hanoi::bot;
+use part3::hanoi::notation::{self, CheckMovesError, MoveRecorder};
+use part3::hanoi::packed::PackedState;
+use part3::hanoi::render::Compact;
+use part3::hanoi::rules::Rules;
+use part3::hanoi::save::SavedGame;
+use part3::hanoi::variant::Variant;
+use part3::hanoi::{
+    moves, moves_bitwise, moves_emulate_recursion, moves_iterative, play, play_with, solve,
+    IllegalMoveError, Session, TowerSelector, TowerSelectorSet, TowerSet,
+};
+
+const A: TowerSelector = TowerSelector::A;
+const B: TowerSelector = TowerSelector::B;
+const C: TowerSelector = TowerSelector::C;
+
+#[test]
+fn test_solvers_agree() {
+    let selectors = TowerSelectorSet::from_parts(A, C, B);
+    for height in 0..=10 {
+        let recursive: Vec<_> = moves(height, selectors).collect();
+        assert_eq!(recursive.len(), (1 << height) - 1);
+        assert_eq!(moves_iterative(height, selectors), recursive);
+        assert_eq!(
+            moves_bitwise(height, selectors).collect::<Vec<_>>(),
+            recursive
+        );
+        assert_eq!(moves_emulate_recursion(height, selectors), recursive);
+
+        let mut count = 0;
+        let towers = solve(
+            TowerSet::with_pegs(3, height),
+            height,
+            selectors,
+            &mut |_, _| count += 1,
+        )
+        .unwrap();
+        assert_eq!(count, recursive.len());
+        assert_eq!(towers, TowerSet::from_positions(3, &vec![C; height]));
+    }
+}
+
+#[test]
+fn test_illegal_moves() {
+    let towers = TowerSet::with_pegs(3, 3);
+    let result = play(towers, [(A, B), (A, B)], &mut |_, _| {});
+    assert_eq!(
+        result,
+        Err(IllegalMoveError::OversizeDisk {
+            source: A,
+            target: B,
+            radius: 2,
+            below: 1,
+        })
+    );
+    let rules: Rules = "cyclic".parse().unwrap();
+    let result = play_with(&rules, TowerSet::with_pegs(3, 1), [(A, C)], &mut |_, _| {});
+    assert_eq!(
+        result,
+        Err(IllegalMoveError::ForbiddenMove {
+            source: A,
+            target: C
+        })
+    );
+}
+
+#[test]
+fn test_notation_round_trip() {
+    let solution: Vec<_> = moves(4, TowerSelectorSet::new()).collect();
+    let mut recorder = MoveRecorder::new(Vec::new());
+    recorder.comment("four disks").unwrap();
+    for &next in &solution {
+        recorder.record(next).unwrap();
+    }
+    let text = String::from_utf8(recorder.finish().unwrap()).unwrap();
+    assert_eq!(notation::parse_moves(&text), Ok(solution.clone()));
+
+    let towers = TowerSet::with_pegs(3, 4);
+    let rules = Rules::Classic;
+    assert!(notation::check_moves(towers.clone(), &solution, &rules, B).is_ok());
+    assert_eq!(
+        notation::check_moves(towers, &solution[..3], &rules, B),
+        Err(CheckMovesError::Unfinished {
+            moves: 3,
+            target: B
+        })
+    );
+}
+
+#[test]
+fn test_session_round_trip() {
+    let mut session = Session::new(TowerSet::with_pegs(3, 3), C);
+    session.play(A, C).unwrap();
+    session.play(A, B).unwrap();
+    let saved: SavedGame = session.to_saved().to_string().parse().unwrap();
+    let mut resumed = Session::from_saved(saved);
+    assert_eq!(resumed.towers, session.towers);
+    assert_eq!(resumed.undo(), Some((A, B)));
+    while let Some((source, target)) = resumed.hint() {
+        resumed.play(source, target).unwrap();
+    }
+    assert!(resumed.is_won());
+    assert_eq!(resumed.summary(), "Solved in 7 moves, which is optimal!");
+}
+
+#[test]
+fn test_bot_game() {
+    let session = Session::new(TowerSet::with_pegs(3, 2), B);
+    let replies = "AC\nAB\nCB\n";
+    let mut output = Vec::new();
+    let game = bot::referee(session, &Compact, Cursor::new(replies), &mut output).unwrap();
+    assert!(game.session.is_won());
+    assert_eq!(game.score(), 100);
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.starts_with("hanoi target B\nstate A[2 1] B[] C[]\nok\n"));
+    assert!(output.ends_with("won 3\n"));
+}
+
+#[test]
+fn test_variants_and_packing() {
+    let variant: Variant = "copies:2".parse().unwrap();
+    let selectors = TowerSelectorSet::new();
+    let towers = variant.start(3, A);
+    let solved = play(towers, variant.moves(3, selectors), &mut |_, _| {}).unwrap();
+    assert!(variant.goal(selectors).is_met(&solved));
+
+    let packed = PackedState::try_from(&TowerSet::with_pegs(3, 5)).unwrap();
+    assert_eq!(packed.successors().count(), 2);
+    assert_eq!(TowerSet::from(packed), TowerSet::with_pegs(3, 5));
+}
@@ -1,5 +1,14 @@
 //! First N Fibonacci numbers using a Y combinator. Inspired by the Python
 //! snippet in the foreword to The Recursive Book of Recursion.
+//!
+//! Alongside `y` are fixpoint combinators for functions of two and three
+//! arguments, used by `ackermann`, `binomial` and `tak`; one for mutually
+//! recursive functions, used by `is_even_is_odd` and
+//! `hofstadter_female_male`; and `memo_y`, which memoizes every call,
+//! recursive or not.
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::hash::Hash;
 use std::rc::Rc;
 
 /// A Y combinator, copied from the Rust Playground at
@@ -33,6 +42,158 @@ where
     })))
 }
 
+/// A fixpoint combinator for functions of two arguments, which `y` ties as a
+/// function of one argument, a pair.
+#[allow(clippy::type_complexity)]
+fn y2<A, B, O, F>(f: Rc<dyn Fn(Rc<dyn Fn(A, B) -> O>) -> F>) -> impl Fn(A, B) -> O
+where
+    F: Fn(A, B) -> O,
+    F: 'static,
+    A: 'static,
+    B: 'static,
+    O: 'static,
+{
+    let paired = y(Rc::new(move |recurse: Rc<dyn Fn((A, B)) -> O>| {
+        let f = f(Rc::new(move |a, b| recurse((a, b))));
+        move |(a, b)| f(a, b)
+    }));
+    move |a, b| paired((a, b))
+}
+
+/// A fixpoint combinator for functions of three arguments, tied the same way
+/// as `y2`, but with a triple.
+#[allow(clippy::type_complexity)]
+fn y3<A, B, C, O, F>(f: Rc<dyn Fn(Rc<dyn Fn(A, B, C) -> O>) -> F>) -> impl Fn(A, B, C) -> O
+where
+    F: Fn(A, B, C) -> O,
+    F: 'static,
+    A: 'static,
+    B: 'static,
+    C: 'static,
+    O: 'static,
+{
+    let tripled = y(Rc::new(move |recurse: Rc<dyn Fn((A, B, C)) -> O>| {
+        let f = f(Rc::new(move |a, b, c| recurse((a, b, c))));
+        move |(a, b, c)| f(a, b, c)
+    }));
+    move |a, b, c| tripled((a, b, c))
+}
+
+/// A function of one argument, as passed to and returned by `y_poly`.
+type Rec<A, O> = Rc<dyn Fn(A) -> O>;
+
+/// A polyvariadic fixpoint combinator, for mutually recursive functions.  Each
+/// of the functionals is passed every one of the functions being defined, in
+/// the same order, and returns its own.  They are tied by `y` as a single
+/// function taking the index of the function to call along with its
+/// argument.
+#[allow(clippy::type_complexity)]
+fn y_poly<A, O>(fs: Vec<Rc<dyn Fn(Vec<Rec<A, O>>) -> Rec<A, O>>>) -> Vec<Rec<A, O>>
+where
+    A: 'static,
+    O: 'static,
+{
+    let count = fs.len();
+    let fs = Rc::new(fs);
+    let indexed = Rc::new(y(Rc::new(move |recurse: Rc<dyn Fn((usize, A)) -> O>| {
+        let each: Vec<Rec<A, O>> = (0..count)
+            .map(|index| {
+                let recurse = Rc::clone(&recurse);
+                Rc::new(move |a| recurse((index, a))) as Rec<A, O>
+            })
+            .collect();
+        let fs = Rc::clone(&fs);
+        move |(index, a): (usize, A)| fs[index](each.clone())(a)
+    })));
+    (0..count)
+        .map(|index| {
+            let indexed = Rc::clone(&indexed);
+            Rc::new(move |a| indexed((index, a))) as Rec<A, O>
+        })
+        .collect()
+}
+
+/// The number of calls a memoized function answered from its cache (hits),
+/// and the number it had to compute (misses).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    /// The number of calls answered from the cache.
+    pub hits: u64,
+    /// The number of calls whose result had to be computed.
+    pub misses: u64,
+}
+
+/// A function built by `memo_y`, which remembers the result for each
+/// argument it has seen, across calls and within the recursion.
+pub struct Memo<A, O> {
+    f: Box<dyn Fn(A) -> O>,
+    cache: Rc<RefCell<HashMap<A, O>>>,
+    stats: Rc<Cell<CacheStats>>,
+}
+
+impl<A, O> Memo<A, O> {
+    /// Calls the function, returning the remembered result if there is one.
+    pub fn call(&self, a: A) -> O {
+        (self.f)(a)
+    }
+
+    /// Returns the hits and misses of every call so far, including those made
+    /// by the recursion.
+    pub fn stats(&self) -> CacheStats {
+        self.stats.get()
+    }
+
+    /// Returns the number of results remembered.
+    pub fn len(&self) -> usize {
+        self.cache.borrow().len()
+    }
+
+    /// Returns whether no results are remembered yet.
+    pub fn is_empty(&self) -> bool {
+        self.cache.borrow().is_empty()
+    }
+}
+
+/// A memoizing fixpoint combinator, with the same shape as `y`.  Each call,
+/// whether from outside or from the recursion, first looks in a cache shared
+/// by them all, so each argument is computed only once.  The cache is not
+/// borrowed while a result is computed, so the recursion may fill it.
+#[allow(clippy::type_complexity)]
+pub fn memo_y<A, O, F>(f: Rc<dyn Fn(Rc<dyn Fn(A) -> O>) -> F>) -> Memo<A, O>
+where
+    F: Fn(A) -> O,
+    F: 'static,
+    A: Clone + Eq + Hash + 'static,
+    O: Clone + 'static,
+{
+    let cache = Rc::new(RefCell::new(HashMap::new()));
+    let stats = Rc::new(Cell::new(CacheStats::default()));
+    let (shared_cache, shared_stats) = (Rc::clone(&cache), Rc::clone(&stats));
+    let memoized = y(Rc::new(move |recurse: Rc<dyn Fn(A) -> O>| {
+        let f = f(recurse);
+        let (cache, stats) = (Rc::clone(&shared_cache), Rc::clone(&shared_stats));
+        move |a: A| -> O {
+            let mut counts = stats.get();
+            let cached = cache.borrow().get(&a).cloned();
+            if let Some(o) = cached {
+                counts.hits += 1;
+                stats.set(counts);
+                return o;
+            }
+            counts.misses += 1;
+            stats.set(counts);
+            let o = f(a.clone());
+            cache.borrow_mut().insert(a, o.clone());
+            o
+        }
+    }));
+    Memo {
+        f: Box::new(memoized),
+        cache,
+        stats,
+    }
+}
+
 fn fib(n: usize) -> u32 {
     let next = |(a, b)| (b, a + b);
     y(Rc::new(move |f: Rc<dyn Fn(usize) -> (u32, u32)>| {
@@ -50,6 +211,99 @@ pub fn fibs(n: usize) -> Vec<u32> {
     (0..n).map(fib).collect()
 }
 
+/// The Ackermann function, which grows faster than any primitive recursive
+/// function.  Only small arguments finish: A(4, 1) recurses 65533 deep.
+pub fn ackermann(m: u64, n: u64) -> u64 {
+    y2(Rc::new(|a: Rc<dyn Fn(u64, u64) -> u64>| {
+        move |m, n| match (m, n) {
+            (0, n) => n + 1,
+            (m, 0) => a(m - 1, 1),
+            (m, n) => a(m - 1, a(m, n - 1)),
+        }
+    }))(m, n)
+}
+
+/// The number of ways to choose k things from n, by Pascal's rule.
+pub fn binomial(n: u64, k: u64) -> u64 {
+    y2(Rc::new(|c: Rc<dyn Fn(u64, u64) -> u64>| {
+        move |n, k| match (n, k) {
+            (_, 0) => 1,
+            (n, k) if k > n => 0,
+            (n, k) if k == n => 1,
+            (n, k) => c(n - 1, k - 1) + c(n - 1, k),
+        }
+    }))(n, k)
+}
+
+/// The Takeuchi function, in McCarthy's form, which makes a great many calls
+/// to return little more than one of its arguments.
+pub fn tak(x: i64, y: i64, z: i64) -> i64 {
+    y3(Rc::new(|t: Rc<dyn Fn(i64, i64, i64) -> i64>| {
+        move |x, y, z| {
+            if y < x {
+                t(t(x - 1, y, z), t(y - 1, z, x), t(z - 1, x, y))
+            } else {
+                z
+            }
+        }
+    }))(x, y, z)
+}
+
+/// Returns whether n is even, and whether it is odd, each defined in terms
+/// of the other.
+pub fn is_even_is_odd(n: u64) -> (bool, bool) {
+    let fns = y_poly(vec![
+        Rc::new(|fns: Vec<Rec<u64, bool>>| {
+            Rc::new(move |n: u64| n == 0 || fns[1](n - 1)) as Rec<u64, bool>
+        }),
+        Rc::new(|fns: Vec<Rec<u64, bool>>| {
+            Rc::new(move |n: u64| n != 0 && fns[0](n - 1)) as Rec<u64, bool>
+        }),
+    ]);
+    (fns[0](n), fns[1](n))
+}
+
+/// Returns the first n terms of Hofstadter's Female and Male sequences, each
+/// defined in terms of the other.
+pub fn hofstadter_female_male(n: usize) -> (Vec<usize>, Vec<usize>) {
+    let fns = y_poly(vec![
+        Rc::new(|fns: Vec<Rec<usize, usize>>| {
+            Rc::new(move |n| match n {
+                0 => 1,
+                n => n - fns[1](fns[0](n - 1)),
+            }) as Rec<usize, usize>
+        }),
+        Rc::new(|fns: Vec<Rec<usize, usize>>| {
+            Rc::new(move |n| match n {
+                0 => 0,
+                n => n - fns[0](fns[1](n - 1)),
+            }) as Rec<usize, usize>
+        }),
+    ]);
+    (
+        (0..n).map(&*fns[0]).collect(),
+        (0..n).map(&*fns[1]).collect(),
+    )
+}
+
+/// Returns a memoized Fibonacci function, written as the doubly recursive
+/// definition, which would take exponential time without the cache.
+pub fn memo_fib() -> Memo<usize, u64> {
+    memo_y(Rc::new(|f: Rc<dyn Fn(usize) -> u64>| {
+        move |n| match n {
+            0 | 1 => n as u64,
+            n => f(n - 1) + f(n - 2),
+        }
+    }))
+}
+
+/// Returns the first n Fibonacci numbers, sharing one cache among them all,
+/// so that each number is computed only once.
+pub fn memo_fibs(n: usize) -> Vec<u64> {
+    let fib = memo_fib();
+    (0..n).map(|n| fib.call(n)).collect()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -60,4 +314,121 @@ mod test {
         // original example, would be prohibitively slow.
         assert_eq!(fibs(10), [0, 1, 1, 2, 3, 5, 8, 13, 21, 34]);
     }
+
+    #[test]
+    fn test_ackermann() {
+        let row = |m| (0..5).map(|n| ackermann(m, n)).collect::<Vec<_>>();
+        assert_eq!(row(0), [1, 2, 3, 4, 5]);
+        assert_eq!(row(1), [2, 3, 4, 5, 6]);
+        assert_eq!(row(2), [3, 5, 7, 9, 11]);
+        assert_eq!(row(3), [5, 13, 29, 61, 125]);
+    }
+
+    #[test]
+    fn test_binomial() {
+        let row = |n| (0..=n).map(|k| binomial(n, k)).collect::<Vec<_>>();
+        assert_eq!(row(0), [1]);
+        assert_eq!(row(4), [1, 4, 6, 4, 1]);
+        assert_eq!(row(10), [1, 10, 45, 120, 210, 252, 210, 120, 45, 10, 1]);
+        assert_eq!(binomial(3, 5), 0);
+    }
+
+    #[test]
+    fn test_tak() {
+        fn direct(x: i64, y: i64, z: i64) -> i64 {
+            if y < x {
+                direct(
+                    direct(x - 1, y, z),
+                    direct(y - 1, z, x),
+                    direct(z - 1, x, y),
+                )
+            } else {
+                z
+            }
+        }
+        for (x, y, z) in [(0, 0, 0), (3, 2, 1), (6, 4, 2), (12, 8, 4), (18, 12, 6)] {
+            assert_eq!(tak(x, y, z), direct(x, y, z));
+        }
+    }
+
+    #[test]
+    fn test_is_even_is_odd() {
+        let evens: Vec<_> = (0..6).map(|n| is_even_is_odd(n).0).collect();
+        let odds: Vec<_> = (0..6).map(|n| is_even_is_odd(n).1).collect();
+        assert_eq!(evens, [true, false, true, false, true, false]);
+        assert_eq!(odds, [false, true, false, true, false, true]);
+        assert_eq!(is_even_is_odd(1001), (false, true));
+    }
+
+    #[test]
+    fn test_hofstadter_female_male() {
+        let (female, male) = hofstadter_female_male(21);
+        assert_eq!(
+            female,
+            [1, 1, 2, 2, 3, 3, 4, 5, 5, 6, 6, 7, 8, 8, 9, 9, 10, 11, 11, 12, 13]
+        );
+        assert_eq!(
+            male,
+            [0, 0, 1, 2, 2, 3, 4, 4, 5, 6, 6, 7, 7, 8, 9, 9, 10, 11, 11, 12, 12]
+        );
+    }
+
+    #[test]
+    fn test_memo_fib() {
+        assert_eq!(memo_fibs(10), [0, 1, 1, 2, 3, 5, 8, 13, 21, 34]);
+        let fibs = memo_fibs(37);
+        assert_eq!(fibs[..10], [0, 1, 1, 2, 3, 5, 8, 13, 21, 34]);
+        assert_eq!(fibs[36], 14_930_352);
+        assert_eq!(memo_fibs(90)[89], 1_779_979_416_004_714_189);
+    }
+
+    #[test]
+    fn test_memo_stats() {
+        // Each number is computed once; every other call, two for each
+        // number from 2 up, is answered from the cache.
+        let fib = memo_fib();
+        assert!(fib.is_empty());
+        for n in 0..90 {
+            fib.call(n);
+        }
+        assert_eq!(fib.len(), 90);
+        assert_eq!(
+            fib.stats(),
+            CacheStats {
+                hits: 2 * 88,
+                misses: 90
+            }
+        );
+        assert_eq!(fib.call(89), 1_779_979_416_004_714_189);
+        assert_eq!(fib.stats().hits, 2 * 88 + 1);
+    }
+
+    #[test]
+    fn test_memo_speed_up() {
+        // Count the calls the doubly recursive definition makes with and
+        // without a cache.
+        let calls = Rc::new(Cell::new(0));
+        let counted = Rc::clone(&calls);
+        let naive = y(Rc::new(move |f: Rc<dyn Fn(usize) -> u64>| {
+            let counted = Rc::clone(&counted);
+            move |n| {
+                counted.set(counted.get() + 1);
+                match n {
+                    0 | 1 => n as u64,
+                    n => f(n - 1) + f(n - 2),
+                }
+            }
+        }));
+        assert_eq!(naive(25), 75_025);
+        // F(n) takes 2F(n + 1) - 1 calls: 242785 for n = 25.
+        assert_eq!(calls.get(), 2 * 121_393 - 1);
+
+        let fib = memo_fib();
+        assert_eq!(fib.call(25), 75_025);
+        // Each number from 2 up is computed once, with two calls, so there
+        // are 2n - 1 calls in all.
+        let CacheStats { hits, misses } = fib.stats();
+        assert_eq!(misses, 26);
+        assert_eq!(hits + misses, 49);
+    }
 }